@@ -0,0 +1,502 @@
+//! A command line client for talking to a seabird instance.
+//!
+//! This is mostly useful during development, for sending a quick message or
+//! watching the events a bot would receive. Run `seabird-cli --help` for the
+//! available commands, or `seabird-cli repl` to run several commands over a
+//! single connection.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use seabird::proto::event::Inner;
+use seabird::render::Dialect;
+use seabird::replay::EventRecorder;
+use seabird::{proto, Block, ClientConfig, SeabirdClient};
+
+#[derive(Debug, Parser)]
+#[command(name = "seabird-cli", version, about = "Talk to a seabird instance")]
+struct Cli {
+    /// The URL of the seabird instance.
+    #[arg(long, env = "SEABIRD_URL")]
+    url: String,
+
+    /// The auth token to connect with.
+    #[arg(long, env = "SEABIRD_TOKEN", hide_env_values = true)]
+    token: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// The commands available from the REPL, which don't take any connection
+/// options.
+#[derive(Debug, Parser)]
+#[command(name = "", no_binary_name = true, disable_version_flag = true)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Send a message to a channel, or to a user with --private.
+    Send {
+        /// Send a private message to this user ID instead of a channel.
+        #[arg(long)]
+        private: bool,
+        /// The channel ID, or user ID with --private.
+        target: String,
+        /// The message text.
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+    },
+    /// Perform an action in a channel, or for a user with --private.
+    Action {
+        /// Perform a private action for this user ID instead of a channel.
+        #[arg(long)]
+        private: bool,
+        /// The channel ID, or user ID with --private.
+        target: String,
+        /// The action text.
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+    },
+    /// Join a channel on a backend.
+    Join {
+        /// The backend ID.
+        backend_id: String,
+        /// The name of the channel to join.
+        channel_name: String,
+    },
+    /// Leave a channel.
+    Leave {
+        /// The channel ID.
+        channel_id: String,
+        /// The message to leave with, if the backend supports one.
+        #[arg(long, default_value = "")]
+        message: String,
+    },
+    /// Check that the core is reachable and accepts the token.
+    Health {
+        /// How long to wait for the core, in seconds.
+        #[arg(long, default_value_t = 5)]
+        timeout: u64,
+    },
+    /// List the connected backends.
+    Backends,
+    /// List the channels on a backend.
+    Channels {
+        /// The backend ID.
+        backend_id: String,
+    },
+    /// Print events as they're received.
+    Tail {
+        /// The output format.
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+        /// Also record the events to this file, for replaying later.
+        #[arg(long)]
+        record: Option<PathBuf>,
+    },
+    /// Read commands from stdin, one per line, over a single connection.
+    Repl,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    /// One formatted line per event.
+    Text,
+    /// One JSON object per event.
+    Json,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let mut client = SeabirdClient::new(ClientConfig {
+        url: cli.url,
+        token: cli.token,
+    })
+    .await?;
+
+    match cli.command {
+        Command::Repl => repl(&mut client).await,
+        command => run(&mut client, command).await,
+    }
+}
+
+async fn repl(client: &mut SeabirdClient) -> anyhow::Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+
+        let Some(line) = lines.next_line().await? else {
+            return Ok(());
+        };
+        let words = match split_words(&line) {
+            Ok(words) if words.is_empty() => continue,
+            Ok(words) => words,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                continue;
+            }
+        };
+        if matches!(words[0].as_str(), "exit" | "quit") {
+            return Ok(());
+        }
+
+        match ReplLine::try_parse_from(words) {
+            Ok(ReplLine {
+                command: Command::Repl,
+            }) => eprintln!("error: already in the REPL"),
+            Ok(ReplLine { command }) => {
+                if let Err(err) = run(client, command).await {
+                    eprintln!("error: {:#}", err);
+                }
+            }
+            Err(err) => {
+                let _ = err.print();
+            }
+        }
+    }
+}
+
+async fn run(client: &mut SeabirdClient, command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Send {
+            private: false,
+            target,
+            text,
+        } => client.send_message(target, text.join(" "), None).await,
+        Command::Send {
+            private: true,
+            target,
+            text,
+        } => {
+            client
+                .send_private_message(target, text.join(" "), None)
+                .await
+        }
+        Command::Action {
+            private: false,
+            target,
+            text,
+        } => client.perform_action(target, text.join(" "), None).await,
+        Command::Action {
+            private: true,
+            target,
+            text,
+        } => {
+            client
+                .perform_private_action(target, text.join(" "), None)
+                .await
+        }
+        Command::Join {
+            backend_id,
+            channel_name,
+        } => client.join_channel(backend_id, channel_name).await,
+        Command::Leave {
+            channel_id,
+            message,
+        } => client.leave_channel(channel_id, message).await,
+        Command::Health { timeout } => {
+            let info = client.health_check(Duration::from_secs(timeout)).await?;
+            println!("up for {}s", info.uptime().as_secs());
+            for service in info.current_services {
+                println!("{}", service);
+            }
+            Ok(())
+        }
+        Command::Backends => {
+            for backend in client.list_backends().await? {
+                println!("{}\t{}", backend.id, backend.backend_type);
+            }
+            Ok(())
+        }
+        Command::Channels { backend_id } => {
+            for channel in client.list_channels(backend_id).await? {
+                println!(
+                    "{}\t{}\t{}",
+                    channel.id, channel.display_name, channel.topic
+                );
+            }
+            Ok(())
+        }
+        Command::Tail { format, record } => {
+            let mut events = client.stream_events(HashMap::new()).await?;
+            if let Some(path) = record {
+                events = events.record_to(EventRecorder::create(path).await?);
+            }
+            while let Some(event) = events.next().await? {
+                match format {
+                    Format::Text => println!("{}", format_event(&event)),
+                    Format::Json => println!("{}", event_json(&event)),
+                }
+            }
+            Ok(())
+        }
+        Command::Repl => anyhow::bail!("the REPL can't be started from here"),
+    }
+}
+
+/// Splits a line into words on whitespace, keeping anything in double quotes
+/// together.
+fn split_words(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            '\\' if quoted => {
+                let escaped = chars.next().context("trailing backslash")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    anyhow::ensure!(!quoted, "unclosed quote");
+    words.extend(word);
+    Ok(words)
+}
+
+/// Returns the message text of an event, preferring its blocks.
+fn message_text(text: &str, root_block: Option<&proto::Block>) -> String {
+    match root_block {
+        Some(block) => Block::from(block.clone()).render(Dialect::Plain),
+        None => text.to_string(),
+    }
+}
+
+fn format_event(event: &proto::Event) -> String {
+    let user = |user: &Option<proto::User>| {
+        user.as_ref()
+            .map(|user| user.display_name.clone())
+            .unwrap_or_default()
+    };
+    let source = |source: &Option<proto::ChannelSource>| match source {
+        Some(source) => format!("{} {}", source.channel_id, user(&source.user)),
+        None => String::new(),
+    };
+
+    match &event.inner {
+        Some(Inner::Message(msg)) => format!(
+            "[message] {}: {}",
+            source(&msg.source),
+            message_text(&msg.text, msg.root_block.as_ref())
+        ),
+        Some(Inner::PrivateMessage(msg)) => format!(
+            "[private_message] {}: {}",
+            user(&msg.source),
+            message_text(&msg.text, msg.root_block.as_ref())
+        ),
+        Some(Inner::Mention(msg)) => format!(
+            "[mention] {}: {}",
+            source(&msg.source),
+            message_text(&msg.text, msg.root_block.as_ref())
+        ),
+        Some(Inner::Command(cmd)) => format!(
+            "[command] {}: {} {}",
+            source(&cmd.source),
+            cmd.command,
+            cmd.arg
+        ),
+        Some(Inner::Action(action)) => format!(
+            "[action] {}: {}",
+            source(&action.source),
+            message_text(&action.text, action.root_block.as_ref())
+        ),
+        Some(Inner::PrivateAction(action)) => format!(
+            "[private_action] {}: {}",
+            user(&action.source),
+            message_text(&action.text, action.root_block.as_ref())
+        ),
+        Some(Inner::SendMessage(msg)) => format!(
+            "[send_message] {} {}: {}",
+            msg.channel_id,
+            msg.sender,
+            message_text(&msg.text, msg.root_block.as_ref())
+        ),
+        Some(Inner::SendPrivateMessage(msg)) => format!(
+            "[send_private_message] {} {}: {}",
+            msg.user_id,
+            msg.sender,
+            message_text(&msg.text, msg.root_block.as_ref())
+        ),
+        Some(Inner::PerformAction(action)) => format!(
+            "[perform_action] {} {}: {}",
+            action.channel_id,
+            action.sender,
+            message_text(&action.text, action.root_block.as_ref())
+        ),
+        Some(Inner::PerformPrivateAction(action)) => format!(
+            "[perform_private_action] {} {}: {}",
+            action.user_id,
+            action.sender,
+            message_text(&action.text, action.root_block.as_ref())
+        ),
+        None => "[unknown]".to_string(),
+    }
+}
+
+fn event_json(event: &proto::Event) -> serde_json::Value {
+    let user = |user: &Option<proto::User>| {
+        user.as_ref()
+            .map(|user| json!({ "id": user.id, "display_name": user.display_name }))
+    };
+    let source = |source: &Option<proto::ChannelSource>| {
+        source
+            .as_ref()
+            .map(|source| json!({ "channel_id": source.channel_id, "user": user(&source.user) }))
+    };
+    let blocks = |block: &Option<proto::Block>| block.clone().map(Block::from);
+
+    let (event_type, body) = match &event.inner {
+        Some(Inner::Message(msg)) => (
+            "message",
+            json!({ "source": source(&msg.source), "text": msg.text, "blocks": blocks(&msg.root_block) }),
+        ),
+        Some(Inner::PrivateMessage(msg)) => (
+            "private_message",
+            json!({ "source": user(&msg.source), "text": msg.text, "blocks": blocks(&msg.root_block) }),
+        ),
+        Some(Inner::Mention(msg)) => (
+            "mention",
+            json!({ "source": source(&msg.source), "text": msg.text, "blocks": blocks(&msg.root_block) }),
+        ),
+        Some(Inner::Command(cmd)) => (
+            "command",
+            json!({ "source": source(&cmd.source), "command": cmd.command, "arg": cmd.arg }),
+        ),
+        Some(Inner::Action(action)) => (
+            "action",
+            json!({ "source": source(&action.source), "text": action.text, "blocks": blocks(&action.root_block) }),
+        ),
+        Some(Inner::PrivateAction(action)) => (
+            "private_action",
+            json!({ "source": user(&action.source), "text": action.text, "blocks": blocks(&action.root_block) }),
+        ),
+        Some(Inner::SendMessage(msg)) => (
+            "send_message",
+            json!({ "sender": msg.sender, "channel_id": msg.channel_id, "text": msg.text, "blocks": blocks(&msg.root_block) }),
+        ),
+        Some(Inner::SendPrivateMessage(msg)) => (
+            "send_private_message",
+            json!({ "sender": msg.sender, "user_id": msg.user_id, "text": msg.text, "blocks": blocks(&msg.root_block) }),
+        ),
+        Some(Inner::PerformAction(action)) => (
+            "perform_action",
+            json!({ "sender": action.sender, "channel_id": action.channel_id, "text": action.text, "blocks": blocks(&action.root_block) }),
+        ),
+        Some(Inner::PerformPrivateAction(action)) => (
+            "perform_private_action",
+            json!({ "sender": action.sender, "user_id": action.user_id, "text": action.text, "blocks": blocks(&action.root_block) }),
+        ),
+        None => ("unknown", json!({})),
+    };
+
+    json!({ "type": event_type, "tags": event.tags, "event": body })
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn message_event(root_block: Option<proto::Block>) -> proto::Event {
+        proto::Event {
+            tags: HashMap::from([("id".to_string(), "1".to_string())]),
+            inner: Some(Inner::Message(proto::MessageEvent {
+                source: Some(proto::ChannelSource {
+                    channel_id: "chan".to_string(),
+                    user: Some(proto::User {
+                        id: "user-1".to_string(),
+                        display_name: "alice".to_string(),
+                    }),
+                }),
+                text: "hello *world*".to_string(),
+                root_block,
+            })),
+        }
+    }
+
+    #[test]
+    fn commands_are_valid() {
+        Cli::command().debug_assert();
+        ReplLine::command().debug_assert();
+    }
+
+    #[test]
+    fn splits_words_with_quotes() {
+        assert_eq!(
+            split_words(r#"send  chan "hello   world" "say \"hi\"" """#).unwrap(),
+            ["send", "chan", "hello   world", r#"say "hi""#, ""]
+        );
+        assert!(split_words(r#"send "unclosed"#).is_err());
+        assert!(split_words(r#"send "trailing\"#).is_err());
+    }
+
+    #[test]
+    fn parses_repl_lines() {
+        let words = split_words(r#"send --private user-1 "hi there" again"#).unwrap();
+        let line = ReplLine::try_parse_from(words).unwrap();
+        assert!(matches!(
+            line.command,
+            Command::Send { private: true, target, text }
+                if target == "user-1" && text == ["hi there", "again"]
+        ));
+
+        assert!(ReplLine::try_parse_from(["send", "chan"]).is_err());
+    }
+
+    #[test]
+    fn formats_events_as_text() {
+        assert_eq!(
+            format_event(&message_event(None)),
+            "[message] chan alice: hello *world*"
+        );
+
+        let block = Block::new().text("hello ").bold("world");
+        assert_eq!(
+            format_event(&message_event(Some(block.into()))),
+            "[message] chan alice: hello world"
+        );
+        assert_eq!(format_event(&proto::Event::default()), "[unknown]");
+    }
+
+    #[test]
+    fn formats_events_as_json() {
+        let block = Block::new().text("hello ").bold("world");
+        assert_eq!(
+            event_json(&message_event(Some(block.into()))),
+            json!({
+                "type": "message",
+                "tags": { "id": "1" },
+                "event": {
+                    "source": {
+                        "channel_id": "chan",
+                        "user": { "id": "user-1", "display_name": "alice" },
+                    },
+                    "text": "hello *world*",
+                    "blocks": [{ "text": "hello " }, { "bold": [{ "text": "world" }] }],
+                },
+            })
+        );
+    }
+}
@@ -1,4 +1,111 @@
+use std::collections::BTreeMap;
+use std::ops::{Add, AddAssign};
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 use crate::proto;
+use crate::render::{format_timestamp, Dialect, Renderer, TimestampStyle};
+use crate::truncate::{self, Cut, TRUNCATION_MARKER};
+use crate::validate::{ValidationErrors, ValidationOptions, Validator};
+use crate::visit::{
+    flatten_containers, normalize_children, walk_block, FindLinks, Flatten, Fold, MapText,
+    Normalize, Visitor, VisitorMut,
+};
+
+/// The URL prefix used to encode user mentions as link blocks.
+pub(crate) const MENTION_URL_PREFIX: &str = "seabird://user/";
+
+/// Returns the mentioned user ID if the given link is a mention.
+pub(crate) fn mention_user_id(link: &proto::LinkBlock) -> Option<&str> {
+    link.url.strip_prefix(MENTION_URL_PREFIX)
+}
+
+/// Details of a block tree which the protocol has no fields for, such as the
+/// numbering of ordered lists and the display style of timestamps.
+///
+/// These are kept alongside the tree rather than in it. Blocks are identified
+/// by their position among blocks of the same kind in a pre-order walk of the
+/// tree, so hints stay attached to the right blocks when a tree is nested
+/// inside another.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Hints {
+    /// The number of the first item of each ordered list, keyed by the
+    /// position of the list among all list blocks.
+    pub(crate) list_starts: BTreeMap<usize, u64>,
+    /// The display style of each styled timestamp, keyed by the position of
+    /// the timestamp among all timestamp blocks.
+    pub(crate) timestamp_styles: BTreeMap<usize, TimestampStyle>,
+}
+
+impl Hints {
+    fn is_empty(&self) -> bool {
+        self.list_starts.is_empty() && self.timestamp_styles.is_empty()
+    }
+
+    /// Adds the hints of a tree which comes after `offset` blocks of each
+    /// kind in this one.
+    fn extend(&mut self, other: Hints, offset: Counts) {
+        self.list_starts.extend(
+            other
+                .list_starts
+                .into_iter()
+                .map(|(i, start)| (i + offset.lists, start)),
+        );
+        self.timestamp_styles.extend(
+            other
+                .timestamp_styles
+                .into_iter()
+                .map(|(i, style)| (i + offset.timestamps, style)),
+        );
+    }
+}
+
+/// The number of blocks of each kind which can have hints.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Counts {
+    pub(crate) lists: usize,
+    pub(crate) timestamps: usize,
+}
+
+impl Counts {
+    /// Counts the blocks of each kind in the given trees.
+    pub(crate) fn of<'a>(blocks: impl IntoIterator<Item = &'a proto::Block>) -> Self {
+        let mut counts = Counts::default();
+        for block in blocks {
+            counts.visit_block(block);
+        }
+        counts
+    }
+}
+
+impl Add for Counts {
+    type Output = Counts;
+
+    fn add(self, other: Counts) -> Counts {
+        Counts {
+            lists: self.lists + other.lists,
+            timestamps: self.timestamps + other.timestamps,
+        }
+    }
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Counts) {
+        *self = *self + other;
+    }
+}
+
+impl<'a> Visitor<'a> for Counts {
+    fn visit_block(&mut self, block: &'a proto::Block) {
+        match &block.inner {
+            Some(proto::block::Inner::List(_)) => self.lists += 1,
+            Some(proto::block::Inner::Timestamp(_)) => self.timestamps += 1,
+            _ => {}
+        }
+        walk_block(self, block);
+    }
+}
 
 /// A builder for creating message blocks.
 ///
@@ -18,11 +125,25 @@ use crate::proto;
 ///     .text("This is ")
 ///     .bold(Block::new().italic("very").text(" important"));
 ///
+/// // Mentions
+/// let block = Block::new()
+///     .text("Thanks, ")
+///     .mention("user-id", "belak");
+///
 /// // Lists
 /// let block = Block::new()
 ///     .text("My list:")
 ///     .list(vec!["Item 1", "Item 2", "Item 3"]);
 ///
+/// // Numbered lists
+/// let block = Block::new().ordered_list(1, vec!["First", "Second"]);
+///
+/// // Tables
+/// let block = Block::new().table(
+///     vec!["Name", "Score"],
+///     vec![vec!["alice", "10"], vec!["bob", "7"]],
+/// );
+///
 /// // Combining blocks with append/prepend
 /// let header = Block::new().heading(1, "Title");
 /// let body = Block::new().text("Content");
@@ -30,9 +151,14 @@ use crate::proto;
 ///     .append(header)
 ///     .append(body);
 /// ```
+///
+/// With the `serde` feature enabled, blocks can be serialized as a list of
+/// nodes, each an object with a single key naming the node type, such as
+/// `[{"text": "Hello "}, {"bold": [{"text": "world"}]}]`.
 #[derive(Clone, Debug, Default)]
 pub struct Block {
     children: Vec<proto::Block>,
+    hints: Hints,
 }
 
 impl Block {
@@ -40,23 +166,72 @@ impl Block {
     pub fn new() -> Self {
         Self {
             children: Vec::new(),
+            hints: Hints::default(),
         }
     }
 
+    /// Returns the top-level blocks in this builder.
+    pub fn children(&self) -> &[proto::Block] {
+        &self.children
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn hints(&self) -> &Hints {
+        &self.hints
+    }
+
     /// Appends blocks from another Block or proto::Block to the end.
     pub fn append(mut self, block: impl Into<Block>) -> Self {
         let block: Block = block.into();
+        if !block.hints.is_empty() {
+            self.hints.extend(block.hints, Counts::of(&self.children));
+        }
         self.children.extend(block.children);
         self
     }
 
     /// Prepends blocks from another Block or proto::Block to the beginning.
-    pub fn prepend(mut self, block: impl Into<Block>) -> Self {
+    pub fn prepend(self, block: impl Into<Block>) -> Self {
         let block: Block = block.into();
-        let mut new_children = block.children;
-        new_children.extend(self.children);
-        self.children = new_children;
-        self
+        block.append(self)
+    }
+
+    /// Converts blocks which are about to become the children of a new block
+    /// at the end of this one, keeping their hints. `own` counts the new
+    /// block itself, which comes before its children in pre-order.
+    fn nest(
+        &mut self,
+        own: Counts,
+        blocks: impl IntoIterator<Item = impl Into<Block>>,
+    ) -> Vec<proto::Block> {
+        let mut nested: Vec<proto::Block> = Vec::new();
+        let mut offset = None;
+        for block in blocks {
+            let block: Block = block.into();
+            if offset.is_none() && !block.hints.is_empty() {
+                offset = Some(Counts::of(&self.children) + own + Counts::of(&nested));
+            }
+            if let Some(offset) = &mut offset {
+                let counts = Counts::of(&block.children);
+                self.hints.extend(block.hints, *offset);
+                *offset += counts;
+            }
+            nested.push(
+                Block {
+                    children: block.children,
+                    hints: Hints::default(),
+                }
+                .into(),
+            );
+        }
+        nested
+    }
+
+    /// Converts the content of a new formatting block at the end of this one.
+    fn nest_one(&mut self, content: impl Into<Block>) -> proto::Block {
+        self.nest(Counts::default(), Some(content))
+            .pop()
+            .unwrap_or_default()
     }
 
     /// Adds a text block to the sequence.
@@ -71,7 +246,7 @@ impl Block {
 
     /// Adds a bold-formatted block.
     pub fn bold(mut self, content: impl Into<Block>) -> Self {
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Bold(Box::new(proto::BoldBlock {
@@ -83,7 +258,7 @@ impl Block {
 
     /// Adds an italic-formatted block.
     pub fn italic(mut self, content: impl Into<Block>) -> Self {
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Italics(Box::new(
@@ -97,7 +272,7 @@ impl Block {
 
     /// Adds an underline-formatted block.
     pub fn underline(mut self, content: impl Into<Block>) -> Self {
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Underline(Box::new(
@@ -111,7 +286,7 @@ impl Block {
 
     /// Adds a strikethrough-formatted block.
     pub fn strikethrough(mut self, content: impl Into<Block>) -> Self {
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Strikethrough(Box::new(
@@ -125,7 +300,7 @@ impl Block {
 
     /// Adds a spoiler-formatted block.
     pub fn spoiler(mut self, content: impl Into<Block>) -> Self {
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Spoiler(Box::new(
@@ -139,7 +314,7 @@ impl Block {
 
     /// Adds a blockquote-formatted block.
     pub fn blockquote(mut self, content: impl Into<Block>) -> Self {
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Blockquote(Box::new(
@@ -180,7 +355,7 @@ impl Block {
     /// Adds a link block with a URL and content.
     pub fn link(mut self, url: impl Into<String>, content: impl Into<Block>) -> Self {
         let url = url.into();
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Link(Box::new(proto::LinkBlock {
@@ -191,9 +366,30 @@ impl Block {
         self
     }
 
+    /// Adds a mention of a user.
+    ///
+    /// Backends with native mentions (such as Discord) render this as a
+    /// mention of the user with the given ID. Mentions are encoded as a link
+    /// block with a `seabird://user/<user_id>` URL and the text
+    /// `@display_name`, which is also the plain text fallback, so backends
+    /// without mention support display `@display_name`, either as plain text
+    /// or as a link to that URL.
+    pub fn mention(mut self, user_id: impl Into<String>, display_name: impl Into<String>) -> Self {
+        let url = format!("{}{}", MENTION_URL_PREFIX, user_id.into());
+        let text = format!("@{}", display_name.into());
+        self.children.push(proto::Block {
+            plain: text.clone(),
+            inner: Some(proto::block::Inner::Link(Box::new(proto::LinkBlock {
+                url,
+                inner: Some(Box::new(Block::new().text(text).into())),
+            }))),
+        });
+        self
+    }
+
     /// Adds a heading block with a level and content.
     pub fn heading(mut self, level: i32, content: impl Into<Block>) -> Self {
-        let inner_block = content.into().into();
+        let inner_block = self.nest_one(content);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Heading(Box::new(
@@ -206,9 +402,15 @@ impl Block {
         self
     }
 
-    /// Adds a list block containing multiple items.
+    /// Adds a bulleted list block containing multiple items.
+    ///
+    /// Items can contain other lists to create nested lists.
     pub fn list(mut self, items: impl IntoIterator<Item = impl Into<Block>>) -> Self {
-        let inner: Vec<proto::Block> = items.into_iter().map(|item| item.into().into()).collect();
+        let own = Counts {
+            lists: 1,
+            ..Counts::default()
+        };
+        let inner = self.nest(own, items);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::List(proto::ListBlock { inner })),
@@ -216,8 +418,159 @@ impl Block {
         self
     }
 
+    /// Adds a numbered list, starting from `start`.
+    ///
+    /// Items can contain other lists to create nested lists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::render::Dialect;
+    /// use seabird::Block;
+    ///
+    /// let block = Block::new().ordered_list(
+    ///     1,
+    ///     vec![
+    ///         Block::new().text("Install"),
+    ///         Block::new()
+    ///             .text("Configure")
+    ///             .list(vec!["Set the URL", "Set the token"]),
+    ///         Block::new().text("Run"),
+    ///     ],
+    /// );
+    ///
+    /// assert_eq!(
+    ///     block.render(Dialect::Markdown),
+    ///     "1. Install\n2. Configure\n   - Set the URL\n   - Set the token\n3. Run"
+    /// );
+    /// ```
+    pub fn ordered_list(
+        self,
+        start: u64,
+        items: impl IntoIterator<Item = impl Into<Block>>,
+    ) -> Self {
+        let mut list = Block::new().list(items);
+        list.hints.list_starts.insert(0, start);
+
+        // The protocol has no ordered lists, so the numbering is only kept in
+        // the hints and in the plain text fallback.
+        let plain = list.render(Dialect::Plain);
+        list.children[0].plain = plain;
+
+        self.append(list)
+    }
+
+    /// Adds a key/value list, with each key in bold.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::render::Dialect;
+    /// use seabird::Block;
+    ///
+    /// let block = Block::new().definition_list(vec![("Temperature", "21°C"), ("Wind", "5 km/h")]);
+    ///
+    /// assert_eq!(
+    ///     block.render(Dialect::Markdown),
+    ///     "- **Temperature**: 21°C\n- **Wind**: 5 km/h"
+    /// );
+    /// ```
+    pub fn definition_list(
+        self,
+        items: impl IntoIterator<Item = (impl Into<Block>, impl Into<Block>)>,
+    ) -> Self {
+        self.list(
+            items
+                .into_iter()
+                .map(|(key, value)| Block::new().bold(key).text(": ").append(value)),
+        )
+    }
+
+    /// Adds a table, laid out as a monospace fenced code block.
+    ///
+    /// Columns are padded based on the display width of their contents, so
+    /// wide characters such as CJK text and emoji stay aligned. If `headers`
+    /// is empty, only the rows are included.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::render::Dialect;
+    /// use seabird::Block;
+    ///
+    /// let block = Block::new().table(
+    ///     vec!["Name", "Score"],
+    ///     vec![vec!["alice", "10"], vec!["bob", "7"]],
+    /// );
+    ///
+    /// assert_eq!(
+    ///     block.render(Dialect::Plain),
+    ///     "Name   Score\n-----  -----\nalice  10\nbob    7"
+    /// );
+    /// ```
+    pub fn table(
+        self,
+        headers: impl IntoIterator<Item = impl Into<String>>,
+        rows: impl IntoIterator<Item = impl IntoIterator<Item = impl Into<String>>>,
+    ) -> Self {
+        let headers: Vec<String> = headers.into_iter().map(Into::into).collect();
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(Into::into).collect())
+            .collect();
+
+        self.fenced_code("", layout_table(&headers, &rows))
+    }
+
     /// Adds a timestamp block.
-    pub fn timestamp(mut self, time: std::time::SystemTime) -> Self {
+    pub fn timestamp(self, time: std::time::SystemTime) -> Self {
+        self.push_timestamp(time, String::new())
+    }
+
+    /// Adds a timestamp block with a hint for how it should be displayed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// use seabird::render::{Dialect, TimestampStyle};
+    /// use seabird::Block;
+    ///
+    /// let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    /// let block = Block::new()
+    ///     .text("Released ")
+    ///     .styled_timestamp(time, TimestampStyle::ShortDate);
+    ///
+    /// assert_eq!(block.render(Dialect::Plain), "Released 2023-11-14");
+    /// assert_eq!(block.render(Dialect::Discord), "Released <t:1700000000:d>");
+    /// ```
+    ///
+    /// Relative timestamps are worked out when the block is rendered, so a
+    /// block built ahead of time doesn't go stale. The protocol has no field
+    /// for the style, so backends which only show the plain text fallback
+    /// show relative timestamps as the full date and time.
+    pub fn styled_timestamp(self, time: std::time::SystemTime, style: TimestampStyle) -> Self {
+        let seconds = time
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let timestamp = prost_types::Timestamp { seconds, nanos: 0 };
+        let plain = match style {
+            TimestampStyle::Relative => format_timestamp(
+                &timestamp,
+                TimestampStyle::LongDateTime,
+                std::time::UNIX_EPOCH,
+            ),
+            style => format_timestamp(&timestamp, style, std::time::UNIX_EPOCH),
+        };
+
+        let mut block = Block::new().push_timestamp(time, plain);
+        block.hints.timestamp_styles.insert(0, style);
+        self.append(block)
+    }
+
+    fn push_timestamp(mut self, time: std::time::SystemTime, plain: String) -> Self {
         let duration = time
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default();
@@ -226,7 +579,7 @@ impl Block {
             nanos: duration.subsec_nanos() as i32,
         };
         self.children.push(proto::Block {
-            plain: String::new(),
+            plain,
             inner: Some(proto::block::Inner::Timestamp(proto::TimestampBlock {
                 inner: Some(timestamp),
             })),
@@ -236,7 +589,7 @@ impl Block {
 
     /// Adds a container block with multiple child blocks.
     pub fn container(mut self, blocks: impl IntoIterator<Item = impl Into<Block>>) -> Self {
-        let inner: Vec<proto::Block> = blocks.into_iter().map(|b| b.into().into()).collect();
+        let inner = self.nest(Counts::default(), blocks);
         self.children.push(proto::Block {
             plain: String::new(),
             inner: Some(proto::block::Inner::Container(proto::ContainerBlock {
@@ -247,6 +600,347 @@ impl Block {
     }
 }
 
+impl Block {
+    /// Calls the visitor for every block in the tree.
+    pub fn visit<'a>(&'a self, visitor: &mut impl Visitor<'a>) {
+        for child in &self.children {
+            visitor.visit_block(child);
+        }
+    }
+
+    /// Calls the visitor for every block in the tree, allowing blocks to be
+    /// modified in place.
+    ///
+    /// If the visitor adds or removes lists or timestamps, ordered lists are
+    /// rendered as bulleted lists and timestamps lose their style afterwards.
+    pub fn visit_mut(&mut self, visitor: &mut impl VisitorMut) {
+        let counts = Counts::of(&self.children);
+        for child in &mut self.children {
+            visitor.visit_block_mut(child);
+        }
+        self.keep_hints_if(counts);
+    }
+
+    /// Rebuilds the block tree by passing each top-level block through the
+    /// given fold.
+    ///
+    /// If the fold adds or removes lists or timestamps, ordered lists are
+    /// rendered as bulleted lists and timestamps lose their style afterwards.
+    pub fn fold(self, folder: &mut impl Fold) -> Self {
+        let counts = Counts::of(&self.children);
+        let mut block = Self {
+            children: self
+                .children
+                .into_iter()
+                .map(|child| folder.fold_block(child))
+                .collect(),
+            hints: self.hints,
+        };
+        block.keep_hints_if(counts);
+        block
+    }
+
+    /// Drops the hints unless the tree still has the given number of hinted
+    /// blocks, since they may no longer refer to the same blocks.
+    fn keep_hints_if(&mut self, counts: Counts) {
+        if Counts::of(&self.children) != counts {
+            self.hints = Hints::default();
+        }
+    }
+
+    /// Replaces the contents of every text block with the result of the given
+    /// function. Code blocks are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::Block;
+    ///
+    /// let block = Block::new()
+    ///     .text("hello ")
+    ///     .bold("world")
+    ///     .map_text(|text| text.to_uppercase());
+    /// ```
+    pub fn map_text(mut self, f: impl FnMut(&str) -> String) -> Self {
+        self.visit_mut(&mut MapText(f));
+        self
+    }
+
+    /// Returns every link in the block tree, in the order they appear.
+    /// Mentions are not included.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::Block;
+    ///
+    /// let block = Block::new()
+    ///     .text("See ")
+    ///     .link("https://example.com", "the docs")
+    ///     .bold(Block::new().link("https://example.org", "this"));
+    ///
+    /// let urls: Vec<&str> = block.find_links().iter().map(|link| link.url.as_str()).collect();
+    /// assert_eq!(urls, ["https://example.com", "https://example.org"]);
+    /// ```
+    pub fn find_links(&self) -> Vec<&proto::LinkBlock> {
+        let mut finder = FindLinks::default();
+        self.visit(&mut finder);
+        finder.0
+    }
+
+    /// Removes nesting of containers within containers, splicing the children
+    /// of nested containers into their parent.
+    pub fn flatten(self) -> Self {
+        let children = self
+            .children
+            .into_iter()
+            .map(|child| Flatten.fold_block(child))
+            .collect();
+        Self {
+            children: flatten_containers(children),
+            hints: self.hints,
+        }
+    }
+}
+
+impl Block {
+    /// Renders the block tree in the given dialect.
+    ///
+    /// See the [`render`](crate::render) module for details.
+    pub fn render(&self, dialect: Dialect) -> String {
+        let mut renderer = Renderer::new(dialect, &self.hints);
+        for child in &self.children {
+            renderer.block(child);
+        }
+        renderer.finish()
+    }
+
+    /// Returns the length of the block tree when rendered in the given
+    /// dialect.
+    ///
+    /// IRC servers limit the length of a line in bytes, so for
+    /// [`Dialect::Irc`] this is the length in bytes of the UTF-8 text. For
+    /// every other dialect it's the number of characters (Unicode scalar
+    /// values).
+    pub fn rendered_len(&self, dialect: Dialect) -> usize {
+        let rendered = self.render(dialect);
+        match dialect {
+            Dialect::Irc => rendered.len(),
+            _ => rendered.chars().count(),
+        }
+    }
+
+    /// Cuts the block tree down so it renders to at most `max_len` in the
+    /// given dialect, ending with "…(truncated)". The length is measured
+    /// the same way as [`rendered_len`](Self::rendered_len).
+    ///
+    /// Blocks which already fit are returned unchanged. Text is cut between
+    /// grapheme clusters and any formatting around the cut is still closed,
+    /// while links, mentions and timestamps are either kept or dropped as a
+    /// whole. Ordered lists and styled timestamps which are kept keep their
+    /// numbering and style.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::render::Dialect;
+    /// use seabird::Block;
+    ///
+    /// let block = Block::new()
+    ///     .text("Status: ")
+    ///     .bold("everything is fine, really")
+    ///     .truncate(34, Dialect::Markdown);
+    ///
+    /// assert_eq!(block.render(Dialect::Markdown), "Status: **everything**…(truncated)");
+    /// assert_eq!(block.rendered_len(Dialect::Markdown), 34);
+    /// ```
+    pub fn truncate(self, max_len: usize, dialect: Dialect) -> Self {
+        if self.rendered_len(dialect) <= max_len {
+            return self;
+        }
+
+        let marker = || proto::Block {
+            plain: String::new(),
+            inner: Some(proto::block::Inner::Text(proto::TextBlock {
+                text: TRUNCATION_MARKER.to_string(),
+            })),
+        };
+        let cut_to = |units: usize| {
+            let mut cut = Cut::new(&self.hints, units);
+            let mut children = cut.blocks(&self.children);
+            children.push(marker());
+            Self {
+                children,
+                hints: cut.finish(),
+            }
+        };
+
+        // Keeping more of the tree never makes it render shorter, so this
+        // finds the most units which still fit along with the marker.
+        let total: usize = self.children.iter().map(truncate::units).sum();
+        let (mut low, mut high) = (0, total);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if cut_to(mid).rendered_len(dialect) <= max_len {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+
+        let truncated = cut_to(low);
+        if truncated.rendered_len(dialect) <= max_len {
+            return truncated;
+        }
+
+        // Not even the marker fits, so as much of it as possible is used.
+        let mut marker = String::new();
+        for grapheme in TRUNCATION_MARKER.graphemes(true) {
+            let longer = marker.clone() + grapheme;
+            if Self::new().text(longer.as_str()).rendered_len(dialect) > max_len {
+                break;
+            }
+            marker = longer;
+        }
+        Self::new().text(marker)
+    }
+
+    /// Simplifies the block tree without changing how it renders.
+    ///
+    /// Adjacent text blocks are merged, empty text blocks are removed, nested
+    /// containers are flattened and containers with a single child are
+    /// replaced by that child.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::{proto, Block};
+    ///
+    /// let block = Block::new()
+    ///     .text("Hello")
+    ///     .text(", ")
+    ///     .container(vec![Block::new().text("world")])
+    ///     .normalize();
+    ///
+    /// assert_eq!(block.children().len(), 1);
+    /// ```
+    pub fn normalize(self) -> Self {
+        let children = self
+            .children
+            .into_iter()
+            .map(|child| Normalize.fold_block(child))
+            .collect();
+        Self {
+            children: normalize_children(children),
+            hints: self.hints,
+        }
+    }
+
+    /// Checks the block tree against the default [`ValidationOptions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns every problem found in the tree, such as heading levels outside
+    /// of 1 to 6, links with invalid or disallowed URLs, formatting blocks
+    /// without content, or nesting deeper than the maximum depth.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use seabird::validate::ValidationError;
+    /// use seabird::Block;
+    ///
+    /// let block = Block::new().heading(9, "Title").bold("");
+    ///
+    /// let errors = block.validate().unwrap_err();
+    /// assert_eq!(
+    ///     errors.errors(),
+    ///     [
+    ///         ValidationError::InvalidHeadingLevel { level: 9 },
+    ///         ValidationError::EmptyFormatting { kind: "bold" },
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        self.validate_with(&ValidationOptions::default())
+    }
+
+    /// Checks the block tree against the given [`ValidationOptions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns every problem found in the tree. See [`Block::validate`].
+    pub fn validate_with(
+        &self,
+        options: &ValidationOptions,
+    ) -> std::result::Result<(), ValidationErrors> {
+        let mut validator = Validator::new(options);
+        self.visit(&mut validator);
+        validator.finish()
+    }
+}
+
+/// Lays out a table as lines of text, padding each column to the display
+/// width of its widest cell.
+fn layout_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    // Cells are laid out on a single line, so any line breaks are flattened.
+    let clean = |cell: &String| cell.replace(['\r', '\n'], " ");
+    let headers: Vec<String> = headers.iter().map(clean).collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(clean).collect())
+        .collect();
+
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .chain(std::iter::once(headers.len()))
+        .max()
+        .unwrap_or_default();
+    let mut widths = vec![0; columns];
+    for row in std::iter::once(&headers).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let mut lines = Vec::with_capacity(rows.len() + 2);
+    let format_row = |row: &[String]| {
+        let mut line = String::new();
+        for (i, width) in widths.iter().enumerate() {
+            let cell = row.get(i).map(String::as_str).unwrap_or_default();
+            if i > 0 {
+                line.push_str("  ");
+            }
+            line.push_str(cell);
+            line.push_str(&" ".repeat(width - cell.width()));
+        }
+        line.trim_end().to_string()
+    };
+
+    if !headers.is_empty() {
+        lines.push(format_row(&headers));
+        lines.push(
+            widths
+                .iter()
+                .map(|width| "-".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("  "),
+        );
+    }
+    for row in &rows {
+        lines.push(format_row(row));
+    }
+
+    lines.join("\n")
+}
+
+/// Converts the builder into a single block.
+///
+/// The protocol has no fields for the numbering of ordered lists or the style
+/// of timestamps, so ordered lists become plain list blocks and timestamps
+/// lose their style. Both are only kept in the plain text fallback of the
+/// block.
 impl From<Block> for proto::Block {
     fn from(block: Block) -> Self {
         // If single child, return it directly
@@ -270,11 +964,13 @@ impl From<proto::Block> for Block {
         if let Some(proto::block::Inner::Container(container)) = block.inner {
             Block {
                 children: container.inner,
+                hints: Hints::default(),
             }
         } else {
             // Otherwise, wrap the single block
             Block {
                 children: vec![block],
+                hints: Hints::default(),
             }
         }
     }
@@ -297,3 +993,166 @@ impl From<Block> for crate::client::MessageContent {
         crate::client::MessageContent::Blocks(block.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_pads_by_display_width() {
+        let block = Block::new().table(
+            vec!["Name", "Note"],
+            vec![vec!["日本", "wide"], vec!["ab", "narrow"]],
+        );
+
+        assert_eq!(
+            block.render(Dialect::Plain),
+            "Name  Note\n----  ------\n日本  wide\nab    narrow"
+        );
+    }
+
+    #[test]
+    fn table_handles_ragged_rows_and_line_breaks() {
+        let block = Block::new().table(
+            Vec::<String>::new(),
+            vec![vec!["a", "two\nlines", "c"], vec!["longer"]],
+        );
+
+        assert_eq!(
+            block.render(Dialect::Markdown),
+            "```\na       two lines  c\nlonger\n```"
+        );
+    }
+
+    #[test]
+    fn definition_list_bolds_keys() {
+        let block = Block::new().definition_list(vec![("Wind", Block::new().italic("calm"))]);
+
+        assert_eq!(block.render(Dialect::Markdown), "- **Wind**: *calm*");
+        assert_eq!(block.render(Dialect::Plain), "- Wind: calm");
+    }
+
+    #[test]
+    fn ordered_lists_number_and_indent_items() {
+        let block = Block::new().ordered_list(
+            9,
+            vec![
+                Block::new().text("nine"),
+                Block::new()
+                    .text("ten")
+                    .ordered_list(1, vec!["a", "b"])
+                    .list(vec!["c"]),
+            ],
+        );
+
+        assert_eq!(
+            block.render(Dialect::Markdown),
+            "9. nine\n10. ten\n    1. a\n    2. b\n    - c"
+        );
+        assert_eq!(
+            block.children()[0].plain,
+            "9. nine\n10. ten\n    1. a\n    2. b\n    - c"
+        );
+    }
+
+    #[test]
+    fn ordered_lists_keep_their_numbering_when_nested() {
+        let steps = Block::new().ordered_list(3, vec!["three", "four"]);
+        let block = Block::new()
+            .list(vec!["bullet"])
+            .blockquote(Block::new().text("Steps:").append(steps.clone()))
+            .prepend(Block::new().list(vec!["first"]))
+            .bold(steps);
+
+        assert_eq!(
+            block.render(Dialect::Plain),
+            "- first\n- bullet\n> Steps:\n> 3. three\n> 4. four\n3. three\n4. four"
+        );
+    }
+
+    #[test]
+    fn list_text_is_not_parsed_as_numbering() {
+        let block = Block::new().list(vec!["1. not a number"]);
+        assert_eq!(block.render(Dialect::Plain), "- 1. not a number");
+
+        let mut list: proto::Block = Block::new().list(vec!["a", "b"]).into();
+        list.plain = "5. a\n6. b".to_string();
+        assert_eq!(Block::from(list).render(Dialect::Plain), "- a\n- b");
+    }
+
+    #[test]
+    fn plain_blocks_render_ordered_lists_as_bullets() {
+        let block: proto::Block = Block::new().ordered_list(1, vec!["a", "b"]).into();
+
+        assert_eq!(block.plain, "1. a\n2. b");
+        assert_eq!(crate::render::render(&block, Dialect::Plain), "- a\n- b");
+    }
+
+    #[test]
+    fn folds_which_remove_lists_drop_numbering() {
+        struct DropFirstList(bool);
+
+        impl Fold for DropFirstList {
+            fn fold_block(&mut self, block: proto::Block) -> proto::Block {
+                match &block.inner {
+                    Some(proto::block::Inner::List(_)) if !self.0 => {
+                        self.0 = true;
+                        Block::new().text("dropped").into()
+                    }
+                    _ => crate::visit::fold_children(self, block),
+                }
+            }
+        }
+
+        let block = Block::new()
+            .list(vec!["a"])
+            .ordered_list(1, vec!["b"])
+            .map_text(|text| text.to_uppercase());
+        assert_eq!(block.render(Dialect::Plain), "- A\n1. B");
+
+        let block = block.fold(&mut DropFirstList(false));
+        assert_eq!(block.render(Dialect::Plain), "dropped\n- B");
+    }
+
+    #[test]
+    fn styled_timestamps_have_readable_fallbacks() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let plain = |style| proto::Block::from(Block::new().styled_timestamp(time, style)).plain;
+
+        assert_eq!(plain(TimestampStyle::ShortDate), "2023-11-14");
+        assert_eq!(plain(TimestampStyle::Time), "22:13 UTC");
+        assert_eq!(
+            plain(TimestampStyle::LongDateTime),
+            "2023-11-14 22:13:20 UTC"
+        );
+        assert_eq!(plain(TimestampStyle::Relative), "2023-11-14 22:13:20 UTC");
+    }
+
+    #[test]
+    fn timestamp_styles_are_not_parsed_from_fallbacks() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let mut block: proto::Block = Block::new().timestamp(time).into();
+        block.plain = "2023-11-14".to_string();
+
+        let block = Block::from(block);
+        assert_eq!(block.render(Dialect::Discord), "<t:1700000000>");
+        assert_eq!(block.render(Dialect::Plain), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn timestamp_styles_follow_their_timestamps() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let block = Block::new()
+            .timestamp(time)
+            .bold(Block::new().styled_timestamp(time, TimestampStyle::Time))
+            .prepend(Block::new().styled_timestamp(time, TimestampStyle::ShortDate))
+            .list(vec![
+                Block::new().styled_timestamp(time, TimestampStyle::Relative)
+            ]);
+
+        assert_eq!(
+            block.render(Dialect::Discord),
+            "<t:1700000000:d><t:1700000000>**<t:1700000000:t>**\n- <t:1700000000:R>"
+        );
+    }
+}
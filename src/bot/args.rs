@@ -0,0 +1,660 @@
+//! Parsing of command arguments.
+//!
+//! [`CommandEvent`](crate::proto::CommandEvent)s carry their arguments as a
+//! single string. [`Args::parse`] splits it into positional arguments and
+//! flags, and the [`FromArg`] and [`FromArgs`] traits extract typed values
+//! from them for command handlers.
+//!
+//! Arguments are separated by whitespace, and can be grouped with single or
+//! double quotes at the start of an argument. Quotes anywhere else are kept
+//! as they are. A backslash escapes the next character anywhere except in
+//! single quotes. Unquoted arguments starting with `--` are flags, either
+//! `--name` or `--name=value`, and a lone `--` stops any further arguments
+//! being treated as flags.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use seabird::bot::args::{Args, FromArgs, Rest};
+//!
+//! let args = Args::parse(r#"10m --silent "tea time" is over"#)?;
+//! assert!(args.has_flag("silent"));
+//!
+//! let (delay, Rest(message)) = <(Duration, Rest)>::from_args(&args)?;
+//! assert_eq!(delay, Duration::from_secs(600));
+//! assert_eq!(message, r#""tea time" is over"#);
+//! # Ok::<(), seabird::bot::args::ArgError>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// An error from parsing or extracting command arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArgError {
+    /// A quote which was opened but never closed.
+    UnclosedQuote,
+    /// A backslash at the end of the arguments.
+    TrailingBackslash,
+    /// A required argument which wasn't given.
+    Missing { expected: String },
+    /// An argument which couldn't be parsed as the expected type.
+    Invalid { value: String, expected: String },
+    /// An argument left over after every expected argument was extracted.
+    Unexpected { value: String },
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::UnclosedQuote => write!(f, "unclosed quote"),
+            ArgError::TrailingBackslash => write!(f, "trailing backslash"),
+            ArgError::Missing { expected } => write!(f, "missing {}", expected),
+            ArgError::Invalid { value, expected } => {
+                write!(f, "expected {}, got {:?}", expected, value)
+            }
+            ArgError::Unexpected { value } => write!(f, "unexpected argument {:?}", value),
+        }
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+#[derive(Clone, Debug)]
+struct Positional {
+    value: String,
+    /// The byte offset of the argument in the raw string, used to find the
+    /// rest of the text from this argument onwards.
+    start: usize,
+}
+
+/// Command arguments split into positional arguments and flags.
+#[derive(Clone, Debug)]
+pub struct Args {
+    raw: String,
+    positional: Vec<Positional>,
+    flags: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    /// Splits a raw argument string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a quote isn't closed or the string ends with a
+    /// backslash.
+    pub fn parse(raw: &str) -> Result<Self, ArgError> {
+        let mut positional = Vec::new();
+        let mut flags = HashMap::new();
+        let mut flags_done = false;
+
+        let mut chars = raw.char_indices().peekable();
+        loop {
+            while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            let Some(&(start, _)) = chars.peek() else {
+                break;
+            };
+
+            let mut value = String::new();
+            let mut quoted = false;
+            while let Some((i, c)) = chars.next_if(|(_, c)| !c.is_whitespace()) {
+                match c {
+                    // Quotes only group text at the start of an argument, so
+                    // apostrophes in words like "I'm" are kept as they are.
+                    '"' | '\'' if i == start => {
+                        quoted = true;
+                        loop {
+                            match chars.next() {
+                                Some((_, end)) if end == c => break,
+                                Some((_, '\\')) if c == '"' => {
+                                    let (_, escaped) =
+                                        chars.next().ok_or(ArgError::UnclosedQuote)?;
+                                    value.push(escaped);
+                                }
+                                Some((_, inner)) => value.push(inner),
+                                None => return Err(ArgError::UnclosedQuote),
+                            }
+                        }
+                    }
+                    '\\' => {
+                        let (_, escaped) = chars.next().ok_or(ArgError::TrailingBackslash)?;
+                        value.push(escaped);
+                    }
+                    c => value.push(c),
+                }
+            }
+
+            if quoted || flags_done || !value.starts_with("--") {
+                positional.push(Positional { value, start });
+            } else if value == "--" {
+                flags_done = true;
+            } else {
+                let flag = &value[2..];
+                match flag.split_once('=') {
+                    Some((name, value)) => flags.insert(name.to_string(), Some(value.to_string())),
+                    None => flags.insert(flag.to_string(), None),
+                };
+            }
+        }
+
+        Ok(Self {
+            raw: raw.to_string(),
+            positional,
+            flags,
+        })
+    }
+
+    /// Returns the raw argument string.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Returns the positional arguments, with any quotes and escapes removed.
+    pub fn positional(&self) -> impl Iterator<Item = &str> {
+        self.positional.iter().map(|arg| arg.value.as_str())
+    }
+
+    /// Returns true if the given flag was passed, with or without a value.
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    /// Returns the value of a `--name=value` flag.
+    pub fn flag(&self, name: &str) -> Option<&str> {
+        self.flags.get(name)?.as_deref()
+    }
+
+    /// Returns a cursor over the positional arguments, for extracting them
+    /// one at a time.
+    pub fn cursor(&self) -> ArgCursor<'_> {
+        ArgCursor {
+            args: self,
+            index: 0,
+        }
+    }
+}
+
+/// A position in the positional arguments of an [`Args`].
+#[derive(Clone, Debug)]
+pub struct ArgCursor<'a> {
+    args: &'a Args,
+    index: usize,
+}
+
+impl<'a> ArgCursor<'a> {
+    /// Returns the next positional argument without consuming it.
+    pub fn peek(&self) -> Option<&'a str> {
+        self.args
+            .positional
+            .get(self.index)
+            .map(|arg| arg.value.as_str())
+    }
+
+    /// Consumes the next positional argument.
+    pub fn next_arg(&mut self) -> Option<&'a str> {
+        let arg = self.peek()?;
+        self.index += 1;
+        Some(arg)
+    }
+
+    /// Consumes every remaining positional argument, returning the raw text
+    /// from the start of the next one to the end of the arguments.
+    ///
+    /// The text is returned as it was typed, including any quotes or flags.
+    pub fn rest(&mut self) -> Option<&'a str> {
+        let start = self.args.positional.get(self.index)?.start;
+        self.index = self.args.positional.len();
+        Some(self.args.raw[start..].trim_end())
+    }
+
+    /// Returns true if every positional argument has been consumed.
+    pub fn is_empty(&self) -> bool {
+        self.index >= self.args.positional.len()
+    }
+}
+
+/// A type which can be extracted from command arguments.
+pub trait FromArg: Sized {
+    /// Extracts a value from the cursor, consuming the arguments it uses.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument is missing or isn't valid.
+    fn from_arg(cursor: &mut ArgCursor<'_>) -> Result<Self, ArgError>;
+
+    /// Describes the argument in a usage message, such as `<integer>`.
+    fn usage() -> String;
+}
+
+/// Consumes the next argument and parses it, describing it as `expected` in
+/// any error.
+fn parse_next<T: std::str::FromStr>(
+    cursor: &mut ArgCursor<'_>,
+    expected: &str,
+) -> Result<T, ArgError> {
+    let value = cursor.next_arg().ok_or_else(|| ArgError::Missing {
+        expected: expected.to_string(),
+    })?;
+    value.parse().map_err(|_| ArgError::Invalid {
+        value: value.to_string(),
+        expected: expected.to_string(),
+    })
+}
+
+macro_rules! impl_from_arg {
+    ($($ty:ty => $expected:literal),* $(,)?) => {
+        $(
+            impl FromArg for $ty {
+                fn from_arg(cursor: &mut ArgCursor<'_>) -> Result<Self, ArgError> {
+                    parse_next(cursor, $expected)
+                }
+
+                fn usage() -> String {
+                    concat!("<", $expected, ">").to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_arg! {
+    i8 => "integer",
+    i16 => "integer",
+    i32 => "integer",
+    i64 => "integer",
+    isize => "integer",
+    u8 => "number",
+    u16 => "number",
+    u32 => "number",
+    u64 => "number",
+    usize => "number",
+    f32 => "decimal",
+    f64 => "decimal",
+    bool => "true/false",
+    String => "text",
+}
+
+/// Parses durations such as `90s`, `5m` or `1h30m`. A number without a unit
+/// is a number of seconds.
+impl FromArg for Duration {
+    fn from_arg(cursor: &mut ArgCursor<'_>) -> Result<Self, ArgError> {
+        let value = cursor.next_arg().ok_or_else(|| ArgError::Missing {
+            expected: "duration".to_string(),
+        })?;
+        parse_duration(value).ok_or_else(|| ArgError::Invalid {
+            value: value.to_string(),
+            expected: "duration".to_string(),
+        })
+    }
+
+    fn usage() -> String {
+        "<duration>".to_string()
+    }
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let amount: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+
+        let unit_len = rest.bytes().take_while(|b| b.is_ascii_alphabetic()).count();
+        let secs = match &rest[..unit_len] {
+            "s" | "sec" | "secs" => 1,
+            "m" | "min" | "mins" => 60,
+            "h" | "hr" | "hrs" => 60 * 60,
+            "d" | "day" | "days" => 24 * 60 * 60,
+            "w" | "week" | "weeks" => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += Duration::from_secs(amount.checked_mul(secs)?);
+    }
+    Some(total)
+}
+
+/// A user mentioned in the arguments, such as `<@1234>` on Discord.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mention {
+    /// The ID of the mentioned user.
+    pub user_id: String,
+}
+
+impl FromArg for Mention {
+    fn from_arg(cursor: &mut ArgCursor<'_>) -> Result<Self, ArgError> {
+        let value = cursor.next_arg().ok_or_else(|| ArgError::Missing {
+            expected: "user mention".to_string(),
+        })?;
+        let user_id = value
+            .strip_prefix("<@")
+            .and_then(|id| id.strip_suffix('>'))
+            .map(|id| id.strip_prefix('!').unwrap_or(id))
+            .filter(|id| !id.is_empty());
+
+        match user_id {
+            Some(user_id) => Ok(Mention {
+                user_id: user_id.to_string(),
+            }),
+            None => Err(ArgError::Invalid {
+                value: value.to_string(),
+                expected: "user mention".to_string(),
+            }),
+        }
+    }
+
+    fn usage() -> String {
+        "<@user>".to_string()
+    }
+}
+
+/// The rest of the arguments as they were typed, which must not be empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rest(pub String);
+
+impl FromArg for Rest {
+    fn from_arg(cursor: &mut ArgCursor<'_>) -> Result<Self, ArgError> {
+        cursor
+            .rest()
+            .map(|rest| Rest(rest.to_string()))
+            .ok_or_else(|| ArgError::Missing {
+                expected: "text".to_string(),
+            })
+    }
+
+    fn usage() -> String {
+        "<text...>".to_string()
+    }
+}
+
+/// An optional argument, which is `None` if there are no arguments left.
+impl<T: FromArg> FromArg for Option<T> {
+    fn from_arg(cursor: &mut ArgCursor<'_>) -> Result<Self, ArgError> {
+        if cursor.is_empty() {
+            Ok(None)
+        } else {
+            T::from_arg(cursor).map(Some)
+        }
+    }
+
+    fn usage() -> String {
+        let usage = T::usage();
+        let inner = usage
+            .strip_prefix('<')
+            .and_then(|usage| usage.strip_suffix('>'))
+            .unwrap_or(&usage);
+        format!("[{}]", inner)
+    }
+}
+
+/// A set of values which can be extracted from the whole of the command
+/// arguments.
+///
+/// This is implemented for tuples of up to six [`FromArg`] types, which fail
+/// if there are any arguments left over, and for [`Args`] itself, which gives
+/// handlers the arguments unparsed.
+pub trait FromArgs: Sized {
+    /// Extracts the values from the arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any argument is missing or isn't valid, or if there
+    /// are arguments left over.
+    fn from_args(args: &Args) -> Result<Self, ArgError>;
+
+    /// Describes the arguments in a usage message, such as
+    /// `<integer> [text]`.
+    fn usage() -> String;
+}
+
+impl FromArgs for Args {
+    fn from_args(args: &Args) -> Result<Self, ArgError> {
+        Ok(args.clone())
+    }
+
+    fn usage() -> String {
+        String::new()
+    }
+}
+
+macro_rules! impl_from_args {
+    ($($ty:ident),*) => {
+        impl<$($ty: FromArg),*> FromArgs for ($($ty,)*) {
+            #[allow(unused_mut, unused_variables)]
+            fn from_args(args: &Args) -> Result<Self, ArgError> {
+                let mut cursor = args.cursor();
+                let values = ($($ty::from_arg(&mut cursor)?,)*);
+                match cursor.peek() {
+                    Some(value) => Err(ArgError::Unexpected {
+                        value: value.to_string(),
+                    }),
+                    None => Ok(values),
+                }
+            }
+
+            fn usage() -> String {
+                let parts: Vec<String> = vec![$($ty::usage()),*];
+                parts.join(" ")
+            }
+        }
+    };
+}
+
+impl_from_args!();
+impl_from_args!(A);
+impl_from_args!(A, B);
+impl_from_args!(A, B, C);
+impl_from_args!(A, B, C, D);
+impl_from_args!(A, B, C, D, E);
+impl_from_args!(A, B, C, D, E, F);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positional(raw: &str) -> Vec<String> {
+        Args::parse(raw)
+            .unwrap()
+            .positional()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn apostrophes_in_words() {
+        assert_eq!(positional("I'm back"), ["I'm", "back"]);
+        assert_eq!(
+            positional(r#"it's a "quoted" word"#),
+            ["it's", "a", "quoted", "word"]
+        );
+
+        let args = Args::parse("I'm back, don't worry").unwrap();
+        let (Rest(rest),) = <(Rest,)>::from_args(&args).unwrap();
+        assert_eq!(rest, "I'm back, don't worry");
+    }
+
+    #[test]
+    fn quoted_arguments() {
+        assert_eq!(
+            positional(r#""tea time" 'is over'"#),
+            ["tea time", "is over"]
+        );
+        assert_eq!(positional(r#""a b"c"#), ["a bc"]);
+    }
+
+    #[test]
+    fn unclosed_quotes() {
+        assert_eq!(
+            Args::parse(r#""tea time"#).unwrap_err(),
+            ArgError::UnclosedQuote
+        );
+        assert_eq!(
+            Args::parse("'tea time").unwrap_err(),
+            ArgError::UnclosedQuote
+        );
+        assert_eq!(
+            Args::parse(r#""tea\"#).unwrap_err(),
+            ArgError::UnclosedQuote
+        );
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        assert_eq!(positional(r#""say \"hi\"""#), [r#"say "hi""#]);
+        assert_eq!(positional(r#"\"hi\""#), [r#""hi""#]);
+        assert_eq!(positional(r"'a\b'"), [r"a\b"]);
+        assert_eq!(
+            Args::parse(r"oops\").unwrap_err(),
+            ArgError::TrailingBackslash
+        );
+    }
+
+    #[test]
+    fn flags() {
+        let args = Args::parse("--silent --delay=10m --empty= message").unwrap();
+        assert!(args.has_flag("silent"));
+        assert_eq!(args.flag("silent"), None);
+        assert_eq!(args.flag("delay"), Some("10m"));
+        assert_eq!(args.flag("empty"), Some(""));
+        assert!(!args.has_flag("missing"));
+        assert_eq!(args.positional().collect::<Vec<_>>(), ["message"]);
+    }
+
+    #[test]
+    fn quoted_flags_are_positional() {
+        let args = Args::parse(r#""--silent""#).unwrap();
+        assert!(!args.has_flag("silent"));
+        assert_eq!(args.positional().collect::<Vec<_>>(), ["--silent"]);
+    }
+
+    #[test]
+    fn double_dash_ends_flags() {
+        let args = Args::parse("--silent -- --delay=10m text").unwrap();
+        assert!(args.has_flag("silent"));
+        assert!(!args.has_flag("delay"));
+        assert_eq!(
+            args.positional().collect::<Vec<_>>(),
+            ["--delay=10m", "text"]
+        );
+    }
+
+    #[test]
+    fn extracts_numbers() {
+        let args = Args::parse("-3 7 2.5 true").unwrap();
+        assert_eq!(
+            <(i32, u8, f64, bool)>::from_args(&args),
+            Ok((-3, 7, 2.5, true))
+        );
+
+        let args = Args::parse("-3").unwrap();
+        assert_eq!(
+            <(u8,)>::from_args(&args),
+            Err(ArgError::Invalid {
+                value: "-3".to_string(),
+                expected: "number".to_string()
+            })
+        );
+        assert_eq!(
+            <(u8,)>::from_args(&Args::parse("300").unwrap())
+                .unwrap_err()
+                .to_string(),
+            r#"expected number, got "300""#
+        );
+    }
+
+    #[test]
+    fn extracts_durations() {
+        let duration = |raw: &str| <(Duration,)>::from_args(&Args::parse(raw).unwrap());
+        assert_eq!(duration("90"), Ok((Duration::from_secs(90),)));
+        assert_eq!(duration("1h30m"), Ok((Duration::from_secs(5400),)));
+        assert_eq!(duration("2days"), Ok((Duration::from_secs(172_800),)));
+        assert_eq!(duration("1w1s"), Ok((Duration::from_secs(604_801),)));
+        for invalid in ["5y", "m", "1.5h", "10m5", "99999999999999999999w"] {
+            assert_eq!(
+                duration(invalid),
+                Err(ArgError::Invalid {
+                    value: invalid.to_string(),
+                    expected: "duration".to_string()
+                }),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn extracts_mentions() {
+        let mention = |raw: &str| <(Mention,)>::from_args(&Args::parse(raw).unwrap());
+        let user = |id: &str| {
+            Ok((Mention {
+                user_id: id.to_string(),
+            },))
+        };
+        assert_eq!(mention("<@1234>"), user("1234"));
+        assert_eq!(mention("<@!1234>"), user("1234"));
+        assert!(mention("<@>").is_err());
+        assert!(mention("@belak").is_err());
+    }
+
+    #[test]
+    fn rest_keeps_the_text_as_typed() {
+        let args = Args::parse(r#"  remind  "me"  --later   to stretch  "#).unwrap();
+        let (first, Rest(rest)) = <(String, Rest)>::from_args(&args).unwrap();
+        assert_eq!(first, "remind");
+        assert_eq!(rest, r#""me"  --later   to stretch"#);
+
+        assert_eq!(
+            <(String, Rest)>::from_args(&Args::parse("remind").unwrap()),
+            Err(ArgError::Missing {
+                expected: "text".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn optional_arguments() {
+        let roll = |raw: &str| <(u32, Option<u32>)>::from_args(&Args::parse(raw).unwrap());
+        assert_eq!(roll("2 6"), Ok((2, Some(6))));
+        assert_eq!(roll("2"), Ok((2, None)));
+        assert!(roll("2 six").is_err());
+    }
+
+    #[test]
+    fn missing_and_leftover_arguments() {
+        assert_eq!(
+            <(String, i64)>::from_args(&Args::parse("name").unwrap()),
+            Err(ArgError::Missing {
+                expected: "integer".to_string()
+            })
+        );
+        assert_eq!(
+            <(String,)>::from_args(&Args::parse("one two").unwrap()),
+            Err(ArgError::Unexpected {
+                value: "two".to_string()
+            })
+        );
+        assert_eq!(<()>::from_args(&Args::parse("--flag").unwrap()), Ok(()));
+        assert!(<()>::from_args(&Args::parse("extra").unwrap()).is_err());
+
+        let args = Args::parse("anything --goes").unwrap();
+        assert_eq!(Args::from_args(&args).unwrap().raw(), "anything --goes");
+    }
+
+    #[test]
+    fn usage_strings() {
+        assert_eq!(<()>::usage(), "");
+        assert_eq!(<(Mention, Duration)>::usage(), "<@user> <duration>");
+        assert_eq!(
+            <(u8, Option<String>, Option<Rest>)>::usage(),
+            "<number> [text] [text...]"
+        );
+        assert_eq!(Args::usage(), "");
+    }
+}
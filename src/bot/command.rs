@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::block::Block;
+use crate::bot::args::{ArgError, Args, FromArgs};
+use crate::bot::conversation::Conversations;
+use crate::bot::middleware::{Check, Middleware};
+use crate::client::{MessageContent, SeabirdClient};
+use crate::error::Result;
+use crate::proto;
+use crate::telemetry;
+
+pub(crate) type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+type Handler = Arc<dyn Fn(CommandContext, Args) -> BoxFuture<Result<()>> + Send + Sync>;
+
+/// A command which can be registered with a [`Bot`](crate::bot::Bot).
+///
+/// The handler is an async function which takes a [`CommandContext`] and a
+/// set of arguments implementing [`FromArgs`], such as a tuple of
+/// [`FromArg`](crate::bot::args::FromArg) types. If the arguments can't be
+/// extracted, the handler isn't called and the user is sent the error along
+/// with the command's usage.
+///
+/// # Examples
+///
+/// ```rust
+/// use seabird::bot::args::Rest;
+/// use seabird::bot::{Command, CommandContext};
+///
+/// let command = Command::new(
+///     "repeat",
+///     |mut ctx: CommandContext, (count, Rest(text)): (u8, Rest)| async move {
+///         ctx.reply(text.repeat(count.into())).await
+///     },
+/// )
+/// .short_help("Repeats some text");
+///
+/// assert_eq!(command.usage(), "repeat <number> <text...>");
+/// ```
+#[derive(Clone)]
+pub struct Command {
+    name: String,
+    short_help: String,
+    full_help: String,
+    usage: String,
+    middleware: Vec<Arc<dyn Middleware>>,
+    handler: Handler,
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .field("short_help", &self.short_help)
+            .field("full_help", &self.full_help)
+            .field("usage", &self.usage)
+            .field("middleware", &self.middleware.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Command {
+    /// Creates a command with the given name and handler.
+    ///
+    /// The usage shown for argument errors is generated from the argument
+    /// types, and can be replaced with [`with_usage`](Self::with_usage).
+    pub fn new<A, F, Fut>(name: impl Into<String>, handler: F) -> Self
+    where
+        A: FromArgs + Send + 'static,
+        F: Fn(CommandContext, A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let usage = format!("{} {}", name, A::usage()).trim_end().to_string();
+        let handler: Handler =
+            Arc::new(
+                move |mut ctx: CommandContext, args: Args| match A::from_args(&args) {
+                    Ok(args) => Box::pin(handler(ctx, args)),
+                    Err(err) => Box::pin(async move { ctx.reply_usage(&err).await }),
+                },
+            );
+
+        Self {
+            name,
+            short_help: String::new(),
+            full_help: String::new(),
+            usage,
+            middleware: Vec::new(),
+            handler,
+        }
+    }
+
+    /// Sets the one line description of the command.
+    pub fn short_help(mut self, short_help: impl Into<String>) -> Self {
+        self.short_help = short_help.into();
+        self
+    }
+
+    /// Sets the detailed description of the command. If this isn't set, the
+    /// usage is used instead.
+    pub fn full_help(mut self, full_help: impl Into<String>) -> Self {
+        self.full_help = full_help.into();
+        self
+    }
+
+    /// Replaces the generated usage, such as `roll <count> <sides>`.
+    pub fn with_usage(mut self, usage: impl Into<String>) -> Self {
+        self.usage = usage.into();
+        self
+    }
+
+    /// Adds a check which runs before the handler, after any added to the
+    /// bot. See [`middleware`](crate::bot::middleware).
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Returns the name of the command.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the usage of the command.
+    pub fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    pub(crate) fn short_help_text(&self) -> &str {
+        &self.short_help
+    }
+
+    pub(crate) fn full_help_text(&self) -> &str {
+        &self.full_help
+    }
+
+    /// Returns the metadata registered with seabird for this command.
+    pub(crate) fn metadata(&self) -> proto::CommandMetadata {
+        let full_help = if self.full_help.is_empty() {
+            format!("Usage: {}", self.usage)
+        } else {
+            self.full_help.clone()
+        };
+
+        proto::CommandMetadata {
+            name: self.name.clone(),
+            short_help: self.short_help.clone(),
+            full_help,
+        }
+    }
+
+    /// Runs the command for an event, if the bot's middleware and then the
+    /// command's own allow it.
+    pub(crate) async fn call(&self, mut ctx: CommandContext, middleware: &[Arc<dyn Middleware>]) {
+        ctx.usage.clone_from(&self.usage);
+
+        for middleware in middleware.iter().chain(&self.middleware) {
+            if let Check::Deny(reply) = middleware.check(&ctx) {
+                telemetry::record_command_denied(&self.name);
+                if let Some(reply) = reply {
+                    if let Err(err) = ctx.reply(reply).await {
+                        telemetry::record_handler_error(&self.name, &err);
+                    }
+                }
+                return;
+            }
+        }
+
+        let result = match Args::parse(&ctx.event.arg) {
+            Ok(args) => (self.handler)(ctx, args).await,
+            Err(err) => ctx.reply_usage(&err).await,
+        };
+        if let Err(err) = result {
+            telemetry::record_handler_error(&self.name, &err);
+        }
+    }
+}
+
+/// The context a command handler is called with.
+#[derive(Clone, Debug)]
+pub struct CommandContext {
+    client: SeabirdClient,
+    event: proto::CommandEvent,
+    tags: HashMap<String, String>,
+    usage: String,
+    conversations: Arc<Conversations>,
+}
+
+impl CommandContext {
+    pub(crate) fn new(
+        client: SeabirdClient,
+        event: proto::CommandEvent,
+        tags: HashMap<String, String>,
+        conversations: Arc<Conversations>,
+    ) -> Self {
+        Self {
+            client,
+            event,
+            tags,
+            usage: String::new(),
+            conversations,
+        }
+    }
+
+    /// Returns the name of the command.
+    pub fn command(&self) -> &str {
+        &self.event.command
+    }
+
+    /// Returns the raw argument string of the command.
+    pub fn raw_args(&self) -> &str {
+        &self.event.arg
+    }
+
+    /// Returns the ID of the channel the command was sent in.
+    pub fn channel_id(&self) -> &str {
+        self.event
+            .source
+            .as_ref()
+            .map_or("", |source| source.channel_id.as_str())
+    }
+
+    /// Returns the ID of the backend the command was sent on, if the channel
+    /// ID includes it. See [`backend_id`](crate::bot::backend_id).
+    pub fn backend_id(&self) -> Option<&str> {
+        crate::bot::backend_id(self.channel_id())
+    }
+
+    /// Returns the user who sent the command.
+    pub fn user(&self) -> Option<&proto::User> {
+        self.event.source.as_ref()?.user.as_ref()
+    }
+
+    /// Returns the ID of the user who sent the command.
+    pub fn user_id(&self) -> &str {
+        self.user().map_or("", |user| user.id.as_str())
+    }
+
+    /// Returns the tags the command event was sent with.
+    pub fn tags(&self) -> &HashMap<String, String> {
+        &self.tags
+    }
+
+    /// Returns the command event.
+    pub fn event(&self) -> &proto::CommandEvent {
+        &self.event
+    }
+
+    /// Returns the usage of the command.
+    pub fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    /// Returns the client, for making any other requests.
+    pub fn client(&mut self) -> &mut SeabirdClient {
+        &mut self.client
+    }
+
+    /// Sends a message to the channel the command was sent in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    pub async fn reply(&mut self, content: impl Into<MessageContent>) -> Result<()> {
+        let channel_id = self.channel_id().to_string();
+        self.client.send_message(channel_id, content, None).await
+    }
+
+    /// Replies with an argument error and the usage of the command.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    pub async fn reply_usage(&mut self, err: &ArgError) -> Result<()> {
+        let message = Block::new()
+            .text(format!("Invalid arguments: {}. Usage: ", err))
+            .inline_code(self.usage.clone());
+        self.reply(message).await
+    }
+
+    /// Waits for the next message from the user who sent the command, in the
+    /// channel it was sent in.
+    ///
+    /// While a handler is waiting, that user's messages in the channel go to
+    /// the handler instead of any other handlers. Returns `None` if no message
+    /// arrives before the timeout, or if another handler starts waiting on the
+    /// same user and channel.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use seabird::bot::{Command, CommandContext};
+    ///
+    /// let pick = Command::new("pick", |mut ctx: CommandContext, ()| async move {
+    ///     ctx.reply("Reply with 1 for tea or 2 for coffee").await?;
+    ///     match ctx.next_message(Duration::from_secs(30)).await {
+    ///         Some(message) if message.text.trim() == "1" => ctx.reply("Tea it is").await,
+    ///         Some(message) if message.text.trim() == "2" => ctx.reply("Coffee it is").await,
+    ///         Some(_) => ctx.reply("That's not an option").await,
+    ///         None => ctx.reply("Never mind then").await,
+    ///     }
+    /// });
+    /// ```
+    pub async fn next_message(&self, timeout: Duration) -> Option<proto::MessageEvent> {
+        self.next_message_from(self.channel_id(), self.user_id(), timeout)
+            .await
+    }
+
+    /// Waits for the next message from the given user in the given channel,
+    /// in the same way as [`next_message`](Self::next_message).
+    pub async fn next_message_from(
+        &self,
+        channel_id: &str,
+        user_id: &str,
+        timeout: Duration,
+    ) -> Option<proto::MessageEvent> {
+        self.conversations.wait(channel_id, user_id, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::bot::args::{Mention, Rest};
+
+    async fn noop<A>(_ctx: CommandContext, _args: A) -> Result<()> {
+        Ok(())
+    }
+
+    #[test]
+    fn usage_is_generated_from_the_arguments() {
+        assert_eq!(Command::new("ping", noop::<()>).usage(), "ping");
+        assert_eq!(
+            Command::new("remind", noop::<(Mention, Duration, Rest)>).usage(),
+            "remind <@user> <duration> <text...>"
+        );
+        assert_eq!(
+            Command::new("raw", noop::<crate::bot::args::Args>)
+                .with_usage("raw [anything]")
+                .usage(),
+            "raw [anything]"
+        );
+    }
+
+    #[test]
+    fn metadata_falls_back_to_the_usage() {
+        let command = Command::new("roll", noop::<(u32,)>).short_help("Rolls a die");
+        assert_eq!(
+            command.metadata(),
+            proto::CommandMetadata {
+                name: "roll".to_string(),
+                short_help: "Rolls a die".to_string(),
+                full_help: "Usage: roll <number>".to_string(),
+            }
+        );
+
+        let command = command.full_help("Rolls a die with the given number of sides.");
+        assert_eq!(
+            command.metadata().full_help,
+            "Rolls a die with the given number of sides."
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use crate::proto;
+
+/// Handlers waiting for the next message from a user in a channel.
+///
+/// Waiters are keyed by channel and user ID. Only one handler can wait on the
+/// same channel and user at a time, and a new waiter replaces the old one.
+#[derive(Debug, Default)]
+pub(crate) struct Conversations {
+    waiters: Mutex<HashMap<(String, String), oneshot::Sender<proto::MessageEvent>>>,
+    closed: AtomicBool,
+}
+
+impl Conversations {
+    /// Waits for the next message from a user in a channel, returning `None`
+    /// if none arrives before the timeout, the waiter is replaced or the bot
+    /// shuts down.
+    pub(crate) async fn wait(
+        &self,
+        channel_id: &str,
+        user_id: &str,
+        timeout: Duration,
+    ) -> Option<proto::MessageEvent> {
+        let (tx, rx) = oneshot::channel();
+        {
+            // Checking under the lock means close can't miss this waiter.
+            let mut waiters = self.lock();
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            waiters.insert((channel_id.to_string(), user_id.to_string()), tx);
+        }
+
+        let result = tokio::time::timeout(timeout, rx).await;
+        if !matches!(result, Ok(Ok(_))) {
+            // Our receiver is gone now, so clean up the sender if it's still
+            // registered. Senders for newer waiters are left alone.
+            self.lock().retain(|_, tx| !tx.is_closed());
+        }
+        result.ok()?.ok()
+    }
+
+    /// Passes a message to the handler waiting on its channel and user, or
+    /// gives it back if there isn't one.
+    pub(crate) fn route(&self, message: proto::MessageEvent) -> Option<proto::MessageEvent> {
+        let Some(source) = &message.source else {
+            return Some(message);
+        };
+        let user_id = source.user.as_ref().map_or("", |user| user.id.as_str());
+        let key = (source.channel_id.clone(), user_id.to_string());
+
+        match self.lock().remove(&key) {
+            Some(tx) => tx.send(message).err(),
+            None => Some(message),
+        }
+    }
+
+    /// Stops every current and future wait, such as when the bot shuts down.
+    pub(crate) fn close(&self) {
+        let mut waiters = self.lock();
+        self.closed.store(true, Ordering::Release);
+        waiters.clear();
+    }
+
+    fn lock(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<(String, String), oneshot::Sender<proto::MessageEvent>>>
+    {
+        self.waiters.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::bot::{Bot, Command, CommandContext};
+    use crate::testing::{command_event, MockSeabird};
+
+    fn message(channel_id: &str, user_id: &str, text: &str) -> proto::MessageEvent {
+        proto::MessageEvent {
+            source: Some(proto::ChannelSource {
+                channel_id: channel_id.to_string(),
+                user: Some(proto::User {
+                    id: user_id.to_string(),
+                    display_name: user_id.to_string(),
+                }),
+            }),
+            text: text.to_string(),
+            root_block: None,
+        }
+    }
+
+    /// Waits on a channel and user in the background, yielding so the
+    /// waiter is registered before returning.
+    async fn waiter(
+        conversations: &Arc<Conversations>,
+        channel_id: &'static str,
+        user_id: &'static str,
+        timeout: Duration,
+    ) -> tokio::task::JoinHandle<Option<proto::MessageEvent>> {
+        let conversations = Arc::clone(conversations);
+        let handle =
+            tokio::spawn(async move { conversations.wait(channel_id, user_id, timeout).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        handle
+    }
+
+    #[tokio::test]
+    async fn messages_go_to_the_matching_waiter() {
+        let conversations = Arc::new(Conversations::default());
+        let waiting = waiter(&conversations, "chan", "alice", Duration::from_secs(5)).await;
+
+        let other_user = message("chan", "bob", "hi");
+        assert_eq!(conversations.route(other_user.clone()), Some(other_user));
+        let other_channel = message("elsewhere", "alice", "hi");
+        assert_eq!(
+            conversations.route(other_channel.clone()),
+            Some(other_channel)
+        );
+
+        assert_eq!(conversations.route(message("chan", "alice", "2")), None);
+        assert_eq!(waiting.await.unwrap().unwrap().text, "2");
+
+        // The waiter only takes a single message.
+        let next = message("chan", "alice", "3");
+        assert_eq!(conversations.route(next.clone()), Some(next));
+    }
+
+    #[tokio::test]
+    async fn waiters_time_out_and_are_cleaned_up() {
+        let conversations = Arc::new(Conversations::default());
+        let waiting = waiter(&conversations, "chan", "alice", Duration::from_millis(20)).await;
+
+        assert_eq!(waiting.await.unwrap(), None);
+        assert!(conversations.lock().is_empty());
+        let late = message("chan", "alice", "too late");
+        assert_eq!(conversations.route(late.clone()), Some(late));
+    }
+
+    #[tokio::test]
+    async fn new_waiters_replace_old_ones() {
+        let conversations = Arc::new(Conversations::default());
+        let first = waiter(&conversations, "chan", "alice", Duration::from_secs(5)).await;
+        let second = waiter(&conversations, "chan", "alice", Duration::from_secs(5)).await;
+
+        assert_eq!(first.await.unwrap(), None);
+        assert_eq!(conversations.route(message("chan", "alice", "hi")), None);
+        assert_eq!(second.await.unwrap().unwrap().text, "hi");
+    }
+
+    #[tokio::test]
+    async fn closing_ends_every_wait() {
+        let conversations = Arc::new(Conversations::default());
+        let waiting = waiter(&conversations, "chan", "alice", Duration::from_secs(5)).await;
+
+        conversations.close();
+        assert_eq!(waiting.await.unwrap(), None);
+        let wait = conversations.wait("chan", "alice", Duration::from_secs(5));
+        assert_eq!(
+            tokio::time::timeout(Duration::from_secs(1), wait).await,
+            Ok(None)
+        );
+    }
+
+    #[tokio::test]
+    async fn handlers_can_wait_for_a_reply() {
+        let (mut mock, client) = MockSeabird::start().await;
+        let bot = Bot::new(client).command(Command::new(
+            "pick",
+            |mut ctx: CommandContext, ()| async move {
+                ctx.reply("Tea or coffee?").await?;
+                match ctx.next_message(Duration::from_secs(5)).await {
+                    Some(message) => ctx.reply(format!("{} it is", message.text)).await,
+                    None => ctx.reply("Never mind then").await,
+                }
+            },
+        ));
+        let bot = tokio::spawn(bot.run());
+
+        mock.send(command_event("pick", ""));
+        mock.wait_for_messages(1).await;
+        mock.send(proto::Event {
+            tags: HashMap::new(),
+            inner: Some(proto::event::Inner::Message(message(
+                "chan", "user-1", "Coffee",
+            ))),
+        });
+        let messages = mock.wait_for_messages(2).await;
+        assert_eq!(messages[1].text, "Coffee it is");
+
+        mock.close();
+        bot.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+//! Composable predicates over events.
+//!
+//! A [`Filter`] decides whether an event should go to a
+//! [`Route`](crate::bot::Route). Filters are built from the constructors on
+//! [`Filter`] and combined with [`and`](Filter::and), [`or`](Filter::or) and
+//! `!`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use regex::Regex;
+//! use seabird::bot::filter::{EventKind, Filter};
+//!
+//! // Messages on the IRC backend which mention a ticket number, unless
+//! // they're from the ticket bot itself.
+//! let filter = Filter::kind(EventKind::Message)
+//!     .and(Filter::backend("irc"))
+//!     .and(Filter::regex(Regex::new(r"#\d+").unwrap()))
+//!     .and(!Filter::user("irc:ticketbot"));
+//! ```
+
+use std::fmt;
+use std::ops::Not;
+use std::sync::Arc;
+
+use regex::Regex;
+
+use crate::proto;
+use crate::proto::event::Inner;
+
+/// The type of an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// A message in a channel.
+    Message,
+    /// A private message to the bot.
+    PrivateMessage,
+    /// A message in a channel which mentions the bot.
+    Mention,
+    /// A command in a channel.
+    Command,
+    /// An action, such as `/me`, in a channel.
+    Action,
+    /// A private action to the bot.
+    PrivateAction,
+    /// A message sent to a channel by another client.
+    SendMessage,
+    /// A private message sent by another client.
+    SendPrivateMessage,
+    /// An action performed in a channel by another client.
+    PerformAction,
+    /// A private action performed by another client.
+    PerformPrivateAction,
+}
+
+impl EventKind {
+    /// Returns the kind of an event, or `None` if it's empty or of a type
+    /// this version doesn't know about.
+    pub fn of(event: &proto::Event) -> Option<Self> {
+        Some(match event.inner.as_ref()? {
+            Inner::Message(_) => EventKind::Message,
+            Inner::PrivateMessage(_) => EventKind::PrivateMessage,
+            Inner::Mention(_) => EventKind::Mention,
+            Inner::Command(_) => EventKind::Command,
+            Inner::Action(_) => EventKind::Action,
+            Inner::PrivateAction(_) => EventKind::PrivateAction,
+            Inner::SendMessage(_) => EventKind::SendMessage,
+            Inner::SendPrivateMessage(_) => EventKind::SendPrivateMessage,
+            Inner::PerformAction(_) => EventKind::PerformAction,
+            Inner::PerformPrivateAction(_) => EventKind::PerformPrivateAction,
+        })
+    }
+}
+
+/// Returns the channel an event happened in, if it happened in one.
+pub(crate) fn event_channel_id(event: &proto::Event) -> Option<&str> {
+    let channel_id = match event.inner.as_ref()? {
+        Inner::Message(event) => &event.source.as_ref()?.channel_id,
+        Inner::Mention(event) => &event.source.as_ref()?.channel_id,
+        Inner::Command(event) => &event.source.as_ref()?.channel_id,
+        Inner::Action(event) => &event.source.as_ref()?.channel_id,
+        Inner::SendMessage(event) => &event.channel_id,
+        Inner::PerformAction(event) => &event.channel_id,
+        Inner::PrivateMessage(_)
+        | Inner::PrivateAction(_)
+        | Inner::SendPrivateMessage(_)
+        | Inner::PerformPrivateAction(_) => return None,
+    };
+    Some(channel_id)
+}
+
+/// Returns the user who caused an event. Events sent by other clients don't
+/// have one.
+pub(crate) fn event_user(event: &proto::Event) -> Option<&proto::User> {
+    match event.inner.as_ref()? {
+        Inner::Message(event) => event.source.as_ref()?.user.as_ref(),
+        Inner::Mention(event) => event.source.as_ref()?.user.as_ref(),
+        Inner::Command(event) => event.source.as_ref()?.user.as_ref(),
+        Inner::Action(event) => event.source.as_ref()?.user.as_ref(),
+        Inner::PrivateMessage(event) => event.source.as_ref(),
+        Inner::PrivateAction(event) => event.source.as_ref(),
+        Inner::SendMessage(_)
+        | Inner::SendPrivateMessage(_)
+        | Inner::PerformAction(_)
+        | Inner::PerformPrivateAction(_) => None,
+    }
+}
+
+/// Returns the text of an event. Commands don't have any text, only a
+/// command name and arguments.
+pub(crate) fn event_text(event: &proto::Event) -> Option<&str> {
+    let text = match event.inner.as_ref()? {
+        Inner::Message(event) => &event.text,
+        Inner::PrivateMessage(event) => &event.text,
+        Inner::Mention(event) => &event.text,
+        Inner::Action(event) => &event.text,
+        Inner::PrivateAction(event) => &event.text,
+        Inner::SendMessage(event) => &event.text,
+        Inner::SendPrivateMessage(event) => &event.text,
+        Inner::PerformAction(event) => &event.text,
+        Inner::PerformPrivateAction(event) => &event.text,
+        Inner::Command(_) => return None,
+    };
+    Some(text)
+}
+
+/// A predicate over events.
+#[derive(Clone)]
+pub struct Filter {
+    predicate: Arc<dyn Fn(&proto::Event) -> bool + Send + Sync>,
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter").finish_non_exhaustive()
+    }
+}
+
+impl Filter {
+    /// Creates a filter from a function.
+    pub fn new(predicate: impl Fn(&proto::Event) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Matches every event.
+    pub fn any() -> Self {
+        Self::new(|_| true)
+    }
+
+    /// Matches events of the given kind.
+    pub fn kind(kind: EventKind) -> Self {
+        Self::new(move |event| EventKind::of(event) == Some(kind))
+    }
+
+    /// Matches messages which mention the bot.
+    pub fn mention() -> Self {
+        Self::kind(EventKind::Mention)
+    }
+
+    /// Matches events on the given backend, based on the channel ID or, for
+    /// private events, the user ID. See
+    /// [`backend_id`](crate::bot::backend_id).
+    pub fn backend(backend_id: impl Into<String>) -> Self {
+        let backend_id = backend_id.into();
+        Self::new(move |event| {
+            let id =
+                event_channel_id(event).or_else(|| event_user(event).map(|user| user.id.as_str()));
+            id.and_then(crate::bot::backend_id) == Some(backend_id.as_str())
+        })
+    }
+
+    /// Matches events in the given channel.
+    pub fn channel(channel_id: impl Into<String>) -> Self {
+        let channel_id = channel_id.into();
+        Self::new(move |event| event_channel_id(event) == Some(channel_id.as_str()))
+    }
+
+    /// Matches events caused by the given user.
+    pub fn user(user_id: impl Into<String>) -> Self {
+        let user_id = user_id.into();
+        Self::new(move |event| event_user(event).is_some_and(|user| user.id == user_id))
+    }
+
+    /// Matches events with text which matches the regex anywhere.
+    pub fn regex(regex: Regex) -> Self {
+        Self::new(move |event| event_text(event).is_some_and(|text| regex.is_match(text)))
+    }
+
+    /// Matches events which match both filters.
+    pub fn and(self, other: Filter) -> Self {
+        Self::new(move |event| self.matches(event) && other.matches(event))
+    }
+
+    /// Matches events which match either filter.
+    pub fn or(self, other: Filter) -> Self {
+        Self::new(move |event| self.matches(event) || other.matches(event))
+    }
+
+    /// Returns whether the event matches the filter.
+    pub fn matches(&self, event: &proto::Event) -> bool {
+        (self.predicate)(event)
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Filter::new(move |event| !self.matches(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn user(id: &str) -> Option<proto::User> {
+        Some(proto::User {
+            id: id.to_string(),
+            display_name: id.to_string(),
+        })
+    }
+
+    fn event(inner: Inner) -> proto::Event {
+        proto::Event {
+            tags: HashMap::new(),
+            inner: Some(inner),
+        }
+    }
+
+    fn message(channel_id: &str, user_id: &str, text: &str) -> proto::Event {
+        event(Inner::Message(proto::MessageEvent {
+            source: Some(proto::ChannelSource {
+                channel_id: channel_id.to_string(),
+                user: user(user_id),
+            }),
+            text: text.to_string(),
+            root_block: None,
+        }))
+    }
+
+    fn private_message(user_id: &str, text: &str) -> proto::Event {
+        event(Inner::PrivateMessage(proto::PrivateMessageEvent {
+            source: user(user_id),
+            text: text.to_string(),
+            root_block: None,
+        }))
+    }
+
+    fn command(channel_id: &str, user_id: &str, arg: &str) -> proto::Event {
+        event(Inner::Command(proto::CommandEvent {
+            source: Some(proto::ChannelSource {
+                channel_id: channel_id.to_string(),
+                user: user(user_id),
+            }),
+            command: "echo".to_string(),
+            arg: arg.to_string(),
+        }))
+    }
+
+    fn sent(channel_id: &str, text: &str) -> proto::Event {
+        event(Inner::SendMessage(proto::SendMessageEvent {
+            sender: "other-client".to_string(),
+            channel_id: channel_id.to_string(),
+            text: text.to_string(),
+            root_block: None,
+        }))
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(
+            EventKind::of(&message("irc:#a", "irc:alice", "hi")),
+            Some(EventKind::Message)
+        );
+        assert_eq!(
+            EventKind::of(&private_message("irc:alice", "hi")),
+            Some(EventKind::PrivateMessage)
+        );
+        assert_eq!(
+            EventKind::of(&proto::Event {
+                tags: HashMap::new(),
+                inner: None,
+            }),
+            None
+        );
+
+        let filter = Filter::kind(EventKind::Message);
+        assert!(filter.matches(&message("irc:#a", "irc:alice", "hi")));
+        assert!(!filter.matches(&command("irc:#a", "irc:alice", "hi")));
+        assert!(!Filter::mention().matches(&message("irc:#a", "irc:alice", "hi")));
+        assert!(Filter::any().matches(&sent("irc:#a", "hi")));
+    }
+
+    #[test]
+    fn backends_come_from_the_channel_or_private_user() {
+        let filter = Filter::backend("irc");
+        assert!(filter.matches(&message("irc:#a", "discord:alice", "hi")));
+        assert!(!filter.matches(&message("discord:1", "irc:alice", "hi")));
+        assert!(filter.matches(&private_message("irc:alice", "hi")));
+        assert!(filter.matches(&sent("irc:#a", "hi")));
+        assert!(!filter.matches(&message("#a", "alice", "hi")));
+    }
+
+    #[test]
+    fn channels_and_users() {
+        assert!(Filter::channel("irc:#a").matches(&command("irc:#a", "irc:alice", "")));
+        assert!(!Filter::channel("irc:#a").matches(&private_message("irc:alice", "hi")));
+
+        let filter = Filter::user("irc:alice");
+        assert!(filter.matches(&message("irc:#a", "irc:alice", "hi")));
+        assert!(filter.matches(&private_message("irc:alice", "hi")));
+        assert!(!filter.matches(&message("irc:#a", "irc:bob", "hi")));
+        assert!(!filter.matches(&sent("irc:#a", "hi")));
+    }
+
+    #[test]
+    fn regexes_match_text_but_not_commands() {
+        let filter = Filter::regex(Regex::new(r"#\d+").unwrap());
+        assert!(filter.matches(&message("irc:#a", "irc:alice", "see #123")));
+        assert!(filter.matches(&sent("irc:#a", "fixed in #4")));
+        assert!(!filter.matches(&message("irc:#a", "irc:alice", "see # 123")));
+        assert!(!filter.matches(&command("irc:#a", "irc:alice", "#123")));
+    }
+
+    #[test]
+    fn combinators() {
+        let irc_message = Filter::kind(EventKind::Message).and(Filter::backend("irc"));
+        assert!(irc_message.matches(&message("irc:#a", "irc:alice", "hi")));
+        assert!(!irc_message.matches(&private_message("irc:alice", "hi")));
+
+        let either = Filter::user("irc:alice").or(Filter::channel("irc:#b"));
+        assert!(either.matches(&message("irc:#a", "irc:alice", "hi")));
+        assert!(either.matches(&message("irc:#b", "irc:bob", "hi")));
+        assert!(!either.matches(&message("irc:#a", "irc:bob", "hi")));
+
+        let not_alice = !Filter::user("irc:alice");
+        assert!(!not_alice.matches(&message("irc:#a", "irc:alice", "hi")));
+        assert!(not_alice.matches(&sent("irc:#a", "hi")));
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::block::Block;
+use crate::bot::command::{Command, CommandContext};
+
+const HELP_NAME: &str = "help";
+const HELP_USAGE: &str = "help [command]";
+const HELP_SHORT_HELP: &str = "Lists the available commands, or shows how to use one";
+
+/// The help text of a registered command.
+#[derive(Clone, Debug)]
+struct HelpEntry {
+    name: String,
+    short_help: String,
+    full_help: String,
+    usage: String,
+}
+
+impl HelpEntry {
+    fn new(command: &Command) -> Self {
+        Self {
+            name: command.name().to_string(),
+            short_help: command.short_help_text().to_string(),
+            full_help: command.full_help_text().to_string(),
+            usage: command.usage().to_string(),
+        }
+    }
+}
+
+/// Builds the built-in help command, listing the given commands along with
+/// itself.
+pub(crate) fn help_command(commands: &HashMap<String, Arc<Command>>) -> Command {
+    let mut entries: Vec<HelpEntry> = commands
+        .values()
+        .map(|command| HelpEntry::new(command))
+        .collect();
+    entries.push(HelpEntry {
+        name: HELP_NAME.to_string(),
+        short_help: HELP_SHORT_HELP.to_string(),
+        full_help: String::new(),
+        usage: HELP_USAGE.to_string(),
+    });
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    let entries: Arc<[HelpEntry]> = entries.into();
+
+    Command::new(
+        HELP_NAME,
+        move |mut ctx: CommandContext, (name,): (Option<String>,)| {
+            let entries = Arc::clone(&entries);
+            async move {
+                let message = match name {
+                    None => command_list(&entries),
+                    Some(name) => match entries.iter().find(|entry| entry.name == name) {
+                        Some(entry) => command_details(entry),
+                        None => Block::new()
+                            .text("Unknown command ")
+                            .inline_code(name)
+                            .text(". Use ")
+                            .inline_code(HELP_NAME)
+                            .text(" to list the available commands."),
+                    },
+                };
+                ctx.reply(message).await
+            }
+        },
+    )
+    .short_help(HELP_SHORT_HELP)
+    .with_usage(HELP_USAGE)
+}
+
+/// Renders every command with its short help.
+fn command_list(entries: &[HelpEntry]) -> Block {
+    Block::new()
+        .text("Available commands:")
+        .list(entries.iter().map(|entry| {
+            let item = Block::new().inline_code(entry.name.clone());
+            if entry.short_help.is_empty() {
+                item
+            } else {
+                item.text(": ").text(entry.short_help.clone())
+            }
+        }))
+}
+
+/// Renders the usage and full help of a single command.
+fn command_details(entry: &HelpEntry) -> Block {
+    let mut details = Vec::new();
+    if !entry.short_help.is_empty() {
+        details.push(Block::new().text(entry.short_help.clone()));
+    }
+    details.push(
+        Block::new()
+            .text("Usage: ")
+            .inline_code(entry.usage.clone()),
+    );
+    if !entry.full_help.is_empty() {
+        details.push(Block::new().text(entry.full_help.clone()));
+    }
+
+    Block::new().bold(entry.name.clone()).list(details)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bot::{Bot, Command, CommandContext};
+    use crate::testing::{command_event, MockSeabird};
+
+    async fn help_replies(args: &[&str], bot: impl FnOnce(Bot) -> Bot) -> Vec<String> {
+        let (mut mock, client) = MockSeabird::start().await;
+        for arg in args {
+            mock.send(command_event("help", arg));
+        }
+        mock.close();
+        bot(Bot::new(client)).run().await.unwrap();
+        mock.messages().into_iter().map(|sent| sent.text).collect()
+    }
+
+    fn roll() -> Command {
+        Command::new("roll", |_ctx: CommandContext, _args: (u32,)| async {
+            Ok(())
+        })
+        .short_help("Rolls a die")
+        .full_help("Rolls a die with the given number of sides.")
+    }
+
+    #[tokio::test]
+    async fn help_is_opt_in() {
+        let replies = help_replies(&[""], |bot| bot.command(roll())).await;
+        assert!(replies.is_empty());
+    }
+
+    #[tokio::test]
+    async fn help_lists_and_describes_commands() {
+        let replies = help_replies(&["", "roll", "nope"], |bot| {
+            bot.with_help().command(roll()).command(Command::new(
+                "ping",
+                |_ctx: CommandContext, _args: ()| async { Ok(()) },
+            ))
+        })
+        .await;
+
+        assert_eq!(
+            replies,
+            [
+                "Available commands:\
+                 \n- help: Lists the available commands, or shows how to use one\
+                 \n- ping\
+                 \n- roll: Rolls a die",
+                "roll\
+                 \n- Rolls a die\
+                 \n- Usage: roll <number>\
+                 \n- Rolls a die with the given number of sides.",
+                "Unknown command nope. Use help to list the available commands.",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn help_is_registered_and_can_be_replaced() {
+        let (mut mock, client) = MockSeabird::start().await;
+        mock.send(command_event("help", ""));
+        mock.close();
+        Bot::new(client)
+            .with_help()
+            .command(Command::new(
+                "help",
+                |mut ctx: CommandContext, _args: ()| async move { ctx.reply("custom").await },
+            ))
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(mock.commands()["help"].full_help, "Usage: help");
+        assert_eq!(mock.messages()[0].text, "custom");
+    }
+}
@@ -0,0 +1,462 @@
+//! Checks which run before a command handler.
+//!
+//! Middleware can be added to a single command with
+//! [`Command::middleware`](crate::bot::Command::middleware), or to every
+//! command with [`Bot::middleware`](crate::bot::Bot::middleware). Bot-wide
+//! middleware runs first, then the command's own, in the order they were
+//! added. The first check to fail stops the command from running, and its
+//! denial reply, if any, is sent to the channel the command came from.
+//!
+//! Any `Fn(&CommandContext) -> Check` can be used as middleware, alongside
+//! the built-in [`Allowlist`], [`ChannelRestriction`] and [`Cooldown`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use seabird::bot::middleware::{Allowlist, Cooldown};
+//! use seabird::bot::{Command, CommandContext};
+//!
+//! let shutdown = Command::new("shutdown", |mut ctx: CommandContext, ()| async move {
+//!     ctx.reply("Shutting down").await
+//! })
+//! .middleware(Allowlist::new().user("admin-user-id"));
+//!
+//! let roll = Command::new("roll", |mut ctx: CommandContext, (sides,): (u32,)| async move {
+//!     ctx.reply(format!("You rolled a d{}", sides)).await
+//! })
+//! .middleware(
+//!     Cooldown::per_user(Duration::from_secs(10)).deny_reply("Slow down, you can roll again soon."),
+//! );
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::bot::command::CommandContext;
+use crate::client::MessageContent;
+
+/// The outcome of a middleware check.
+#[derive(Clone, Debug)]
+pub enum Check {
+    /// Lets the command continue to the next check, then the handler.
+    Allow,
+    /// Stops the command, sending the reply if there is one.
+    Deny(Option<MessageContent>),
+}
+
+impl Check {
+    /// Denies the command with a reply.
+    pub fn deny(reply: impl Into<MessageContent>) -> Self {
+        Check::Deny(Some(reply.into()))
+    }
+
+    /// Denies the command without replying.
+    pub fn deny_silently() -> Self {
+        Check::Deny(None)
+    }
+}
+
+/// A check which runs before a command handler.
+pub trait Middleware: Send + Sync + 'static {
+    /// Checks whether the command in the context may run.
+    fn check(&self, ctx: &CommandContext) -> Check;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(&CommandContext) -> Check + Send + Sync + 'static,
+{
+    fn check(&self, ctx: &CommandContext) -> Check {
+        self(ctx)
+    }
+}
+
+/// Restricts a command to a set of users and backends.
+///
+/// A command passes if the user is in the allowed users and the backend is in
+/// the allowed backends. An empty set allows everything, so an allowlist with
+/// only users allows those users on any backend.
+#[derive(Clone, Debug)]
+pub struct Allowlist {
+    users: HashSet<String>,
+    backends: HashSet<String>,
+    reply: Option<MessageContent>,
+}
+
+impl Default for Allowlist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Allowlist {
+    /// Creates an allowlist which allows everything until users or backends
+    /// are added.
+    pub fn new() -> Self {
+        Self {
+            users: HashSet::new(),
+            backends: HashSet::new(),
+            reply: Some("You don't have permission to use this command.".into()),
+        }
+    }
+
+    /// Allows a user by ID.
+    pub fn user(mut self, user_id: impl Into<String>) -> Self {
+        self.users.insert(user_id.into());
+        self
+    }
+
+    /// Allows several users by ID.
+    pub fn users(mut self, user_ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.users.extend(user_ids.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allows a backend by ID.
+    pub fn backend(mut self, backend_id: impl Into<String>) -> Self {
+        self.backends.insert(backend_id.into());
+        self
+    }
+
+    /// Sets the reply sent when a command is denied.
+    pub fn deny_reply(mut self, reply: impl Into<MessageContent>) -> Self {
+        self.reply = Some(reply.into());
+        self
+    }
+
+    /// Denies commands without replying.
+    pub fn deny_silently(mut self) -> Self {
+        self.reply = None;
+        self
+    }
+}
+
+impl Middleware for Allowlist {
+    fn check(&self, ctx: &CommandContext) -> Check {
+        let user_allowed = self.users.is_empty() || self.users.contains(ctx.user_id());
+        let backend_allowed = self.backends.is_empty()
+            || ctx
+                .backend_id()
+                .is_some_and(|backend_id| self.backends.contains(backend_id));
+
+        if user_allowed && backend_allowed {
+            Check::Allow
+        } else {
+            Check::Deny(self.reply.clone())
+        }
+    }
+}
+
+/// Restricts which channels a command can be used in.
+#[derive(Clone, Debug)]
+pub struct ChannelRestriction {
+    channels: HashSet<String>,
+    allow: bool,
+    reply: Option<MessageContent>,
+}
+
+impl ChannelRestriction {
+    /// Allows the command only in the given channels.
+    pub fn only(channel_ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self::new(channel_ids, true)
+    }
+
+    /// Allows the command in every channel except the given ones.
+    pub fn except(channel_ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self::new(channel_ids, false)
+    }
+
+    fn new(channel_ids: impl IntoIterator<Item = impl Into<String>>, allow: bool) -> Self {
+        Self {
+            channels: channel_ids.into_iter().map(Into::into).collect(),
+            allow,
+            reply: Some("This command can't be used in this channel.".into()),
+        }
+    }
+
+    /// Sets the reply sent when a command is denied.
+    pub fn deny_reply(mut self, reply: impl Into<MessageContent>) -> Self {
+        self.reply = Some(reply.into());
+        self
+    }
+
+    /// Denies commands without replying.
+    pub fn deny_silently(mut self) -> Self {
+        self.reply = None;
+        self
+    }
+}
+
+impl Middleware for ChannelRestriction {
+    fn check(&self, ctx: &CommandContext) -> Check {
+        if self.channels.contains(ctx.channel_id()) == self.allow {
+            Check::Allow
+        } else {
+            Check::Deny(self.reply.clone())
+        }
+    }
+}
+
+/// What a [`Cooldown`] is tracked per.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CooldownScope {
+    User,
+    Channel,
+}
+
+/// Limits how often a command can be used.
+///
+/// Cooldowns are tracked separately for each command, so one cooldown can be
+/// shared across every command with [`Bot::middleware`](crate::bot::Bot::middleware).
+/// A use only starts the cooldown if every check before it passed, so
+/// cooldowns should usually be added after any other middleware.
+#[derive(Debug)]
+pub struct Cooldown {
+    duration: Duration,
+    scope: CooldownScope,
+    reply: Option<MessageContent>,
+    silent: bool,
+    last_used: Mutex<HashMap<(String, String), Instant>>,
+}
+
+impl Cooldown {
+    /// Limits each user to one use of a command per `duration`, across every
+    /// channel.
+    pub fn per_user(duration: Duration) -> Self {
+        Self::new(duration, CooldownScope::User)
+    }
+
+    /// Limits each channel to one use of a command per `duration`, across
+    /// every user.
+    pub fn per_channel(duration: Duration) -> Self {
+        Self::new(duration, CooldownScope::Channel)
+    }
+
+    fn new(duration: Duration, scope: CooldownScope) -> Self {
+        Self {
+            duration,
+            scope,
+            reply: None,
+            silent: false,
+            last_used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the reply sent when a command is denied. By default, the reply
+    /// says how long is left on the cooldown.
+    pub fn deny_reply(mut self, reply: impl Into<MessageContent>) -> Self {
+        self.reply = Some(reply.into());
+        self.silent = false;
+        self
+    }
+
+    /// Denies commands without replying.
+    pub fn deny_silently(mut self) -> Self {
+        self.silent = true;
+        self
+    }
+}
+
+impl Middleware for Cooldown {
+    fn check(&self, ctx: &CommandContext) -> Check {
+        let scope_id = match self.scope {
+            CooldownScope::User => ctx.user_id(),
+            CooldownScope::Channel => ctx.channel_id(),
+        };
+        let key = (ctx.command().to_string(), scope_id.to_string());
+        let now = Instant::now();
+
+        let mut last_used = self.last_used.lock().unwrap_or_else(|err| err.into_inner());
+        last_used.retain(|_, used| now.duration_since(*used) < self.duration);
+
+        if let Some(used) = last_used.get(&key) {
+            let remaining = self.duration - now.duration_since(*used);
+            if self.silent {
+                return Check::Deny(None);
+            }
+            return match &self.reply {
+                Some(reply) => Check::Deny(Some(reply.clone())),
+                None => Check::deny(format!(
+                    "Please wait {}s before using this command again.",
+                    remaining.as_secs_f64().ceil()
+                )),
+            };
+        }
+
+        last_used.insert(key, now);
+        Check::Allow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::bot::{Bot, Command};
+    use crate::proto;
+    use crate::testing::{command_event, MockSeabird};
+
+    fn from(command: &str, channel_id: &str, user_id: &str) -> proto::Event {
+        let mut event = command_event(command, "");
+        if let Some(proto::event::Inner::Command(command)) = &mut event.inner {
+            let source = command.source.as_mut().unwrap();
+            source.channel_id = channel_id.to_string();
+            source.user.as_mut().unwrap().id = user_id.to_string();
+        }
+        event
+    }
+
+    fn ping() -> Command {
+        Command::new("ping", |mut ctx: CommandContext, ()| async move {
+            ctx.reply("pong").await
+        })
+    }
+
+    /// Runs a bot over the events, returning the replies as
+    /// `channel: text`.
+    async fn replies(bot: impl FnOnce(Bot) -> Bot, events: Vec<proto::Event>) -> Vec<String> {
+        let (mut mock, client) = MockSeabird::start().await;
+        let bot = tokio::spawn(bot(Bot::new(client)).run());
+        // Handlers run concurrently, so events are spaced out to keep the
+        // checks and replies in order.
+        for event in events {
+            mock.send(event);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        mock.close();
+        bot.await.unwrap().unwrap();
+        mock.messages()
+            .into_iter()
+            .map(|sent| format!("{}: {}", sent.target_id, sent.text))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn allowlists_check_users_and_backends() {
+        let allowlist = Allowlist::new().user("admin").backend("irc");
+        let replies = replies(
+            |bot| bot.command(ping().middleware(allowlist)),
+            vec![
+                from("ping", "irc:#chan", "admin"),
+                from("ping", "irc:#chan", "someone"),
+                from("ping", "discord:123", "admin"),
+                from("ping", "chan", "admin"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            replies,
+            [
+                "irc:#chan: pong",
+                "irc:#chan: You don't have permission to use this command.",
+                "discord:123: You don't have permission to use this command.",
+                "chan: You don't have permission to use this command.",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn channel_restrictions_allow_or_exclude_channels() {
+        let replies = replies(
+            |bot| {
+                bot.command(ping().middleware(ChannelRestriction::only(["allowed"])))
+                    .command(
+                        Command::new("echo", |mut ctx: CommandContext, ()| async move {
+                            ctx.reply("echo").await
+                        })
+                        .middleware(ChannelRestriction::except(["allowed"]).deny_silently()),
+                    )
+            },
+            vec![
+                from("ping", "allowed", "user"),
+                from("ping", "other", "user"),
+                from("echo", "allowed", "user"),
+                from("echo", "other", "user"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            replies,
+            [
+                "allowed: pong",
+                "other: This command can't be used in this channel.",
+                "other: echo",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cooldowns_are_per_command_and_scope() {
+        let replies = replies(
+            |bot| {
+                bot.middleware(Cooldown::per_user(Duration::from_secs(60)))
+                    .command(ping())
+                    .command(Command::new(
+                        "echo",
+                        |mut ctx: CommandContext, ()| async move { ctx.reply("echo").await },
+                    ))
+            },
+            vec![
+                from("ping", "chan", "alice"),
+                from("ping", "other", "alice"),
+                from("ping", "chan", "bob"),
+                from("echo", "chan", "alice"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            replies,
+            [
+                "chan: pong",
+                "other: Please wait 60s before using this command again.",
+                "chan: pong",
+                "chan: echo",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cooldowns_expire() {
+        let replies = replies(
+            |bot| bot.command(ping().middleware(Cooldown::per_channel(Duration::from_millis(1)))),
+            vec![from("ping", "chan", "alice"), from("ping", "chan", "bob")],
+        )
+        .await;
+
+        assert_eq!(replies, ["chan: pong", "chan: pong"]);
+    }
+
+    #[tokio::test]
+    async fn bot_middleware_runs_first_and_the_first_denial_wins() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let record = |name: &'static str, check: fn() -> Check| {
+            let calls = Arc::clone(&calls);
+            move |_ctx: &CommandContext| {
+                calls.lock().unwrap().push(name);
+                check()
+            }
+        };
+
+        let replies = replies(
+            |bot| {
+                bot.middleware(record("bot", || Check::Allow)).command(
+                    ping()
+                        .middleware(record("first", || Check::deny("denied")))
+                        .middleware(record("second", || Check::Allow)),
+                )
+            },
+            vec![from("ping", "chan", "alice")],
+        )
+        .await;
+
+        assert_eq!(replies, ["chan: denied"]);
+        assert_eq!(*calls.lock().unwrap(), ["bot", "first"]);
+    }
+}
@@ -0,0 +1,494 @@
+//! A framework for writing bots on top of the event stream.
+//!
+//! A [`Bot`] registers a set of [`Command`]s with seabird, then reads the
+//! event stream and runs the matching command handler for each command event.
+//! Other events can be handled with [`Route`]s, which send events matching a
+//! [`Filter`](filter::Filter) to a handler. Handlers run concurrently, each
+//! with its own context for replying.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use seabird::bot::args::Rest;
+//! use seabird::bot::{Bot, Command, CommandContext};
+//! use seabird::{ClientConfig, SeabirdClient};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = SeabirdClient::new(ClientConfig {
+//!         url: "https://seabird.example.com".to_string(),
+//!         token: "your-token-here".to_string(),
+//!     })
+//!     .await?;
+//!
+//!     Bot::new(client)
+//!         .with_help()
+//!         .command(
+//!             Command::new("echo", |mut ctx: CommandContext, (Rest(text),): (Rest,)| async move {
+//!                 ctx.reply(text).await
+//!             })
+//!             .short_help("Repeats what you said"),
+//!         )
+//!         .run()
+//!         .await?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinSet;
+
+use crate::client::SeabirdClient;
+use crate::error::Result;
+use crate::proto;
+use crate::proto::event::Inner;
+use crate::stream::EventStream;
+use crate::telemetry;
+
+pub mod args;
+mod command;
+mod conversation;
+pub mod filter;
+mod help;
+pub mod middleware;
+mod route;
+
+pub use command::{Command, CommandContext};
+pub use route::{EventContext, Route};
+
+use conversation::Conversations;
+use middleware::Middleware;
+
+/// How long running handlers are given to finish when a bot shuts down.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns the backend ID from a channel or user ID assigned by the core.
+///
+/// The core makes IDs unique across backends by prefixing them with the
+/// backend ID, as in `<backend_id>:<id>`. This returns `None` for IDs without
+/// a prefix.
+pub fn backend_id(id: &str) -> Option<&str> {
+    id.split_once(':').map(|(backend_id, _)| backend_id)
+}
+
+/// Dispatches events from the event stream to handlers.
+pub struct Bot {
+    client: SeabirdClient,
+    commands: HashMap<String, Arc<Command>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    routes: Vec<Route>,
+    conversations: Arc<Conversations>,
+    help: bool,
+    drain_timeout: Duration,
+}
+
+impl fmt::Debug for Bot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bot")
+            .field("client", &self.client)
+            .field("commands", &self.commands)
+            .field("middleware", &self.middleware.len())
+            .field("routes", &self.routes)
+            .field("help", &self.help)
+            .field("drain_timeout", &self.drain_timeout)
+            .finish()
+    }
+}
+
+impl Bot {
+    /// Creates a bot which uses the given client for the event stream and
+    /// for replies.
+    pub fn new(client: SeabirdClient) -> Self {
+        Self {
+            client,
+            commands: HashMap::new(),
+            middleware: Vec::new(),
+            routes: Vec::new(),
+            conversations: Arc::new(Conversations::default()),
+            help: false,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+
+    /// Registers a command, replacing any command with the same name.
+    pub fn command(mut self, command: Command) -> Self {
+        self.commands
+            .insert(command.name().to_string(), Arc::new(command));
+        self
+    }
+
+    /// Adds a route for events matching a filter. See [`Route`] for the order
+    /// routes are tried in.
+    ///
+    /// Routes see every event, including commands, except for messages which
+    /// go to a handler waiting on the conversation.
+    pub fn route(mut self, route: Route) -> Self {
+        // Keep routes sorted by priority, and in insertion order within the
+        // same priority.
+        let index = self
+            .routes
+            .partition_point(|existing| existing.priority_value() >= route.priority_value());
+        self.routes.insert(index, route);
+        self
+    }
+
+    /// Adds a check which runs before every command, including the built-in
+    /// help command. See [`middleware`].
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Enables the built-in `help` command.
+    ///
+    /// `help` replies with a list of every registered command and its short
+    /// help, and `help <command>` replies with the usage and full help of a
+    /// single command. A command registered with the name `help` takes
+    /// precedence over the built-in one.
+    pub fn with_help(mut self) -> Self {
+        self.help = true;
+        self
+    }
+
+    /// Sets how long to wait for running handlers to finish when the bot
+    /// shuts down, before cancelling them. Defaults to 30 seconds.
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Returns the client used by the bot.
+    pub fn client(&self) -> &SeabirdClient {
+        &self.client
+    }
+
+    /// Registers the commands with seabird and handles events until the
+    /// stream is closed, then waits for any running handlers to finish.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream can't be opened or fails.
+    pub async fn run(self) -> Result<()> {
+        self.run_until(std::future::pending()).await
+    }
+
+    /// Registers the commands with seabird and handles events until the
+    /// stream is closed or `shutdown` completes.
+    ///
+    /// Once `shutdown` completes, no more events are read. Running handlers
+    /// are given until the [drain timeout](Self::with_drain_timeout) to
+    /// finish, and any still running after that are cancelled. Handlers
+    /// waiting for a message with
+    /// [`next_message`](CommandContext::next_message) stop waiting straight
+    /// away. The stream is closed once the handlers are done.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream can't be opened or fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use seabird::bot::Bot;
+    /// use seabird::SeabirdClient;
+    /// use tokio::sync::oneshot;
+    ///
+    /// # async fn example(client: SeabirdClient) -> seabird::error::Result<()> {
+    /// // Send on this from a signal handler, such as one for SIGTERM.
+    /// let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    ///
+    /// Bot::new(client)
+    ///     .with_drain_timeout(Duration::from_secs(5))
+    ///     .run_until(async {
+    ///         let _ = shutdown_rx.await;
+    ///     })
+    ///     .await
+    /// # }
+    /// ```
+    pub async fn run_until(mut self, shutdown: impl Future<Output = ()> + Send) -> Result<()> {
+        self.add_help();
+        let commands = self
+            .commands
+            .values()
+            .map(|command| (command.name().to_string(), command.metadata()))
+            .collect();
+        let events = self.client.clone().stream_events(commands).await?;
+        self.run_stream_until(events, shutdown).await
+    }
+
+    /// Handles events from an existing stream, such as a replayed recording,
+    /// until it's closed, then waits for any running handlers to finish.
+    ///
+    /// Commands aren't registered with seabird, so they must have been
+    /// registered when the stream was opened.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream fails.
+    pub async fn run_stream(self, events: EventStream) -> Result<()> {
+        self.run_stream_until(events, std::future::pending()).await
+    }
+
+    /// Handles events from an existing stream until it's closed or
+    /// `shutdown` completes, shutting down in the same way as
+    /// [`run_until`](Self::run_until).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream fails.
+    pub async fn run_stream_until(
+        mut self,
+        mut events: EventStream,
+        shutdown: impl Future<Output = ()> + Send,
+    ) -> Result<()> {
+        self.add_help();
+        let mut shutdown = std::pin::pin!(shutdown);
+        let mut tasks = JoinSet::new();
+        let result = loop {
+            tokio::select! {
+                biased;
+
+                () = &mut shutdown => break Ok(()),
+                event = events.next() => match event {
+                    Ok(Some(event)) => {
+                        // Clean up any finished handlers as we go.
+                        while tasks.try_join_next().is_some() {}
+                        self.dispatch(event, &mut tasks);
+                    }
+                    Ok(None) => break Ok(()),
+                    Err(err) => break Err(err),
+                },
+            }
+        };
+
+        self.conversations.close();
+        let drained = tokio::time::timeout(self.drain_timeout, async {
+            while tasks.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            telemetry::record_handlers_cancelled(tasks.len());
+            tasks.shutdown().await;
+        }
+
+        drop(events);
+        result
+    }
+
+    /// Registers the built-in help command if it's enabled, once every other
+    /// command is known.
+    fn add_help(&mut self) {
+        if self.help && !self.commands.contains_key("help") {
+            let help = help::help_command(&self.commands);
+            self.commands
+                .insert(help.name().to_string(), Arc::new(help));
+        }
+    }
+
+    fn dispatch(&self, event: proto::Event, tasks: &mut JoinSet<()>) {
+        let proto::Event { inner, tags } = event;
+        let inner = match inner {
+            // Messages go to a handler waiting on the conversation if there
+            // is one, and nowhere else.
+            Some(Inner::Message(message)) => match self.conversations.route(message) {
+                Some(message) => Some(Inner::Message(message)),
+                None => return,
+            },
+            inner => inner,
+        };
+        let event = Arc::new(proto::Event { inner, tags });
+
+        for route in self.routes.iter().filter(|route| route.matches(&event)) {
+            let ctx = EventContext::new(
+                self.client.clone(),
+                Arc::clone(&event),
+                Arc::clone(&self.conversations),
+            );
+            let handler = route.call(ctx);
+            tasks.spawn(async move {
+                if let Err(err) = handler.await {
+                    telemetry::record_handler_error("route", &err);
+                }
+            });
+
+            if !route.falls_through() {
+                break;
+            }
+        }
+
+        if let Some(Inner::Command(command_event)) = &event.inner {
+            if let Some(command) = self.commands.get(&command_event.command) {
+                let command = Arc::clone(command);
+                let ctx = CommandContext::new(
+                    self.client.clone(),
+                    command_event.clone(),
+                    event.tags.clone(),
+                    Arc::clone(&self.conversations),
+                );
+                let middleware = self.middleware.clone();
+                tasks.spawn(async move { command.call(ctx, &middleware).await });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{command_event, MockSeabird};
+
+    fn bot(client: SeabirdClient) -> Bot {
+        Bot::new(client)
+            .command(
+                Command::new(
+                    "roll",
+                    |mut ctx: CommandContext, (sides,): (u32,)| async move {
+                        ctx.reply(format!("{} rolled a d{}", ctx.user_id(), sides))
+                            .await
+                    },
+                )
+                .short_help("Rolls a die"),
+            )
+            .command(Command::new(
+                "fail",
+                |_ctx: CommandContext, _args: ()| async move { Err(anyhow::anyhow!("broken")) },
+            ))
+    }
+
+    #[tokio::test]
+    async fn commands_are_registered_with_their_metadata() {
+        let (mut mock, client) = MockSeabird::start().await;
+        mock.close();
+        bot(client).run().await.unwrap();
+
+        let commands = mock.commands();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands["roll"].short_help, "Rolls a die");
+        assert_eq!(commands["fail"].full_help, "Usage: fail");
+    }
+
+    #[tokio::test]
+    async fn handlers_reply_in_the_source_channel() {
+        let (mut mock, client) = MockSeabird::start().await;
+        mock.send(command_event("roll", "20"));
+        mock.close();
+        bot(client).run().await.unwrap();
+
+        let messages = mock.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].target_id, "chan");
+        assert_eq!(messages[0].text, "user-1 rolled a d20");
+    }
+
+    #[tokio::test]
+    async fn argument_errors_reply_with_the_usage() {
+        let (mut mock, client) = MockSeabird::start().await;
+        mock.send(command_event("roll", "lots"));
+        mock.close();
+        bot(client).run().await.unwrap();
+
+        let messages = mock.messages();
+        assert_eq!(messages.len(), 1);
+        assert!(
+            messages[0].text.starts_with("Invalid arguments: "),
+            "{}",
+            messages[0].text
+        );
+        assert!(messages[0].text.ends_with("Usage: roll <number>"));
+    }
+
+    #[tokio::test]
+    async fn other_events_and_handler_errors_are_ignored() {
+        let (mut mock, client) = MockSeabird::start().await;
+        mock.send(command_event("unknown", ""));
+        mock.send(proto::Event {
+            tags: HashMap::new(),
+            inner: Some(Inner::Message(proto::MessageEvent::default())),
+        });
+        mock.send(command_event("fail", ""));
+        mock.send(command_event("roll", "6"));
+
+        let bot = tokio::spawn(bot(client).run());
+        let messages = mock.wait_for_messages(1).await;
+        assert_eq!(messages[0].text, "user-1 rolled a d6");
+
+        mock.close();
+        bot.await.unwrap().unwrap();
+        assert_eq!(mock.messages().len(), 1);
+    }
+
+    fn sleeper(client: SeabirdClient) -> Bot {
+        Bot::new(client).command(Command::new(
+            "sleep",
+            |mut ctx: CommandContext, (millis,): (u64,)| async move {
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+                ctx.reply(format!("slept {millis}ms")).await
+            },
+        ))
+    }
+
+    #[tokio::test]
+    async fn shutdown_waits_for_running_handlers() {
+        let (mock, client) = MockSeabird::start().await;
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let bot = tokio::spawn(sleeper(client).run_until(async {
+            let _ = shutdown_rx.await;
+        }));
+
+        mock.send(command_event("sleep", "100"));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown_tx.send(()).unwrap();
+        // Events after the shutdown aren't handled.
+        mock.send(command_event("sleep", "0"));
+        bot.await.unwrap().unwrap();
+
+        let texts: Vec<_> = mock.messages().into_iter().map(|sent| sent.text).collect();
+        assert_eq!(texts, ["slept 100ms"]);
+    }
+
+    #[tokio::test]
+    async fn handlers_past_the_drain_timeout_are_cancelled() {
+        let (mock, client) = MockSeabird::start().await;
+        let bot = sleeper(client).with_drain_timeout(Duration::from_millis(50));
+
+        mock.send(command_event("sleep", "10"));
+        mock.send(command_event("sleep", "5000"));
+        let started = std::time::Instant::now();
+        bot.run_until(tokio::time::sleep(Duration::from_millis(20)))
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let texts: Vec<_> = mock.messages().into_iter().map(|sent| sent.text).collect();
+        assert_eq!(texts, ["slept 10ms"]);
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_handlers_waiting_for_messages() {
+        let (mock, client) = MockSeabird::start().await;
+        let bot = Bot::new(client).command(Command::new(
+            "ask",
+            |mut ctx: CommandContext, ()| async move {
+                match ctx.next_message(Duration::from_secs(60)).await {
+                    Some(_) => ctx.reply("answered").await,
+                    None => ctx.reply("gave up").await,
+                }
+            },
+        ));
+
+        mock.send(command_event("ask", ""));
+        bot.run_until(tokio::time::sleep(Duration::from_millis(50)))
+            .await
+            .unwrap();
+
+        assert_eq!(mock.messages()[0].text, "gave up");
+    }
+}
@@ -0,0 +1,324 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+
+use crate::bot::command::BoxFuture;
+use crate::bot::conversation::Conversations;
+use crate::bot::filter::{self, EventKind, Filter};
+use crate::client::{MessageContent, SeabirdClient};
+use crate::error::Result;
+use crate::proto;
+
+type Handler = Arc<dyn Fn(EventContext) -> BoxFuture<Result<()>> + Send + Sync>;
+
+/// A handler for events matching a [`Filter`].
+///
+/// When an event arrives, routes are tried from the highest priority to the
+/// lowest, with routes of equal priority tried in the order they were added.
+/// The first matching route handles the event, and routes after it are only
+/// tried if it [falls through](Self::fall_through).
+///
+/// # Examples
+///
+/// ```rust
+/// use regex::Regex;
+/// use seabird::bot::filter::Filter;
+/// use seabird::bot::{EventContext, Route};
+///
+/// // Log every message, then let other routes see it too.
+/// let log = Route::new(Filter::any(), |ctx: EventContext| async move {
+///     println!("{:?}", ctx.text());
+///     Ok(())
+/// })
+/// .priority(100)
+/// .fall_through();
+///
+/// let greet = Route::new(
+///     Filter::regex(Regex::new(r"(?i)^(hi|hello)\b").unwrap()),
+///     |mut ctx: EventContext| async move { ctx.reply("Hello!").await },
+/// );
+/// ```
+#[derive(Clone)]
+pub struct Route {
+    filter: Filter,
+    priority: i32,
+    fall_through: bool,
+    handler: Handler,
+}
+
+impl fmt::Debug for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route")
+            .field("filter", &self.filter)
+            .field("priority", &self.priority)
+            .field("fall_through", &self.fall_through)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Route {
+    /// Creates a route with a priority of 0 which doesn't fall through.
+    pub fn new<F, Fut>(filter: Filter, handler: F) -> Self
+    where
+        F: Fn(EventContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        Self {
+            filter,
+            priority: 0,
+            fall_through: false,
+            handler: Arc::new(move |ctx| Box::pin(handler(ctx))),
+        }
+    }
+
+    /// Sets the priority of the route. Routes with a higher priority are
+    /// tried first.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Lets lower priority routes handle events this route handles.
+    pub fn fall_through(mut self) -> Self {
+        self.fall_through = true;
+        self
+    }
+
+    pub(crate) fn priority_value(&self) -> i32 {
+        self.priority
+    }
+
+    pub(crate) fn falls_through(&self) -> bool {
+        self.fall_through
+    }
+
+    pub(crate) fn matches(&self, event: &proto::Event) -> bool {
+        self.filter.matches(event)
+    }
+
+    pub(crate) fn call(&self, ctx: EventContext) -> BoxFuture<Result<()>> {
+        (self.handler)(ctx)
+    }
+}
+
+/// The context a route handler is called with.
+#[derive(Clone, Debug)]
+pub struct EventContext {
+    client: SeabirdClient,
+    event: Arc<proto::Event>,
+    conversations: Arc<Conversations>,
+}
+
+impl EventContext {
+    pub(crate) fn new(
+        client: SeabirdClient,
+        event: Arc<proto::Event>,
+        conversations: Arc<Conversations>,
+    ) -> Self {
+        Self {
+            client,
+            event,
+            conversations,
+        }
+    }
+
+    /// Returns the event.
+    pub fn event(&self) -> &proto::Event {
+        &self.event
+    }
+
+    /// Returns the kind of the event.
+    pub fn kind(&self) -> Option<EventKind> {
+        EventKind::of(&self.event)
+    }
+
+    /// Returns the ID of the channel the event happened in, or `None` for
+    /// private events.
+    pub fn channel_id(&self) -> Option<&str> {
+        filter::event_channel_id(&self.event)
+    }
+
+    /// Returns the user who caused the event, or `None` for events sent by
+    /// other clients.
+    pub fn user(&self) -> Option<&proto::User> {
+        filter::event_user(&self.event)
+    }
+
+    /// Returns the ID of the backend the event happened on, if it's known.
+    /// See [`backend_id`](crate::bot::backend_id).
+    pub fn backend_id(&self) -> Option<&str> {
+        self.channel_id()
+            .or_else(|| self.user().map(|user| user.id.as_str()))
+            .and_then(crate::bot::backend_id)
+    }
+
+    /// Returns the text of the event, or `None` for commands.
+    pub fn text(&self) -> Option<&str> {
+        filter::event_text(&self.event)
+    }
+
+    /// Returns the client, for making any other requests.
+    pub fn client(&mut self) -> &mut SeabirdClient {
+        &mut self.client
+    }
+
+    /// Replies to the event, in the channel it happened in or privately to
+    /// the user for private events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the event has no channel or user to reply to, or
+    /// if the gRPC request fails.
+    pub async fn reply(&mut self, content: impl Into<MessageContent>) -> Result<()> {
+        if let Some(channel_id) = self.channel_id() {
+            let channel_id = channel_id.to_string();
+            return self.client.send_message(channel_id, content, None).await;
+        }
+
+        let user_id = self
+            .user()
+            .map(|user| user.id.clone())
+            .context("Event has no channel or user to reply to")?;
+        self.client
+            .send_private_message(user_id, content, None)
+            .await
+    }
+
+    /// Waits for the next message from the user who caused the event, in the
+    /// channel it happened in. See
+    /// [`CommandContext::next_message`](crate::bot::CommandContext::next_message).
+    ///
+    /// Returns `None` straight away for events without a channel and user.
+    pub async fn next_message(&self, timeout: Duration) -> Option<proto::MessageEvent> {
+        let channel_id = self.channel_id()?;
+        let user_id = &self.user()?.id;
+        self.conversations.wait(channel_id, user_id, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::bot::{Bot, Command, CommandContext};
+    use crate::proto::event::Inner;
+    use crate::testing::{command_event, MockSeabird};
+
+    type Calls = Arc<Mutex<Vec<&'static str>>>;
+
+    /// A route which records its name when it's called.
+    fn route(calls: &Calls, name: &'static str, filter: Filter) -> Route {
+        let calls = Arc::clone(calls);
+        Route::new(filter, move |_ctx: EventContext| {
+            calls.lock().unwrap().push(name);
+            async { Ok(()) }
+        })
+    }
+
+    fn private_message(text: &str) -> proto::Event {
+        proto::Event {
+            tags: HashMap::new(),
+            inner: Some(Inner::PrivateMessage(proto::PrivateMessageEvent {
+                source: Some(proto::User {
+                    id: "user-1".to_string(),
+                    display_name: "alice".to_string(),
+                }),
+                text: text.to_string(),
+                root_block: None,
+            })),
+        }
+    }
+
+    async fn run(bot: Bot, mut mock: MockSeabird, events: Vec<proto::Event>) -> MockSeabird {
+        for event in events {
+            mock.send(event);
+        }
+        mock.close();
+        bot.run().await.unwrap();
+        mock
+    }
+
+    #[tokio::test]
+    async fn routes_are_tried_by_priority_then_insertion_order() {
+        let calls = Calls::default();
+        let (mock, client) = MockSeabird::start().await;
+        let bot = Bot::new(client)
+            .route(route(&calls, "low", Filter::any()).priority(-1))
+            .route(route(&calls, "first", Filter::any()).fall_through())
+            .route(route(&calls, "second", Filter::any()).fall_through())
+            .route(
+                route(&calls, "high", Filter::any())
+                    .priority(10)
+                    .fall_through(),
+            )
+            .route(route(&calls, "unmatched", !Filter::any()).priority(20))
+            .route(route(&calls, "last", Filter::any()));
+        run(bot, mock, vec![private_message("hi")]).await;
+
+        assert_eq!(*calls.lock().unwrap(), ["high", "first", "second", "last"]);
+    }
+
+    #[tokio::test]
+    async fn the_first_route_without_fall_through_stops_the_rest() {
+        let calls = Calls::default();
+        let (mock, client) = MockSeabird::start().await;
+        let bot = Bot::new(client)
+            .route(route(&calls, "stop", Filter::kind(EventKind::PrivateMessage)).priority(1))
+            .route(route(&calls, "rest", Filter::any()));
+        run(
+            bot,
+            mock,
+            vec![private_message("hi"), command_event("x", "")],
+        )
+        .await;
+
+        assert_eq!(*calls.lock().unwrap(), ["stop", "rest"]);
+    }
+
+    #[tokio::test]
+    async fn commands_run_alongside_routes() {
+        let calls = Calls::default();
+        let (mock, client) = MockSeabird::start().await;
+        let bot = Bot::new(client)
+            .route(route(&calls, "route", Filter::kind(EventKind::Command)))
+            .command(Command::new(
+                "ping",
+                |mut ctx: CommandContext, ()| async move { ctx.reply("pong").await },
+            ));
+        let mock = run(bot, mock, vec![command_event("ping", "")]).await;
+
+        assert_eq!(*calls.lock().unwrap(), ["route"]);
+        assert_eq!(mock.messages()[0].text, "pong");
+    }
+
+    #[tokio::test]
+    async fn replies_go_to_the_channel_or_the_user() {
+        let (mock, client) = MockSeabird::start().await;
+        let echo = Route::new(Filter::any(), |mut ctx: EventContext| async move {
+            let text = format!("{:?}: {}", ctx.kind().unwrap(), ctx.text().unwrap_or(""));
+            ctx.reply(text).await
+        });
+        let bot = Bot::new(client).route(echo);
+        let mock = run(
+            bot,
+            mock,
+            vec![command_event("x", ""), private_message("hi")],
+        )
+        .await;
+
+        let messages = mock.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].target_id, "chan");
+        assert_eq!(messages[0].text, "Command: ");
+
+        let private_messages = mock.private_messages();
+        assert_eq!(private_messages.len(), 1);
+        assert_eq!(private_messages[0].target_id, "user-1");
+        assert_eq!(private_messages[0].text, "PrivateMessage: hi");
+    }
+}
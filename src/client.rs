@@ -1,5 +1,6 @@
 use anyhow::Context;
 use std::collections::HashMap;
+use std::time::Instant;
 
 use http::Uri;
 use tonic::{
@@ -9,12 +10,24 @@ use tonic::{
 
 use crate::error::Result;
 use crate::proto;
+#[cfg(feature = "seabird-client")]
+use crate::tags::Tags;
+use crate::telemetry;
 
 /// MessageContent represents either plain text or structured blocks for messages.
-#[derive(Debug)]
+///
+/// With the `serde` feature enabled, this serializes as either
+/// `{"text": "..."}` or `{"blocks": [...]}`, using the same block format as
+/// [`Block`](crate::Block).
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum MessageContent {
     Text(String),
-    Blocks(proto::Block),
+    Blocks(#[cfg_attr(feature = "serde", serde(with = "crate::serde::proto_block"))] proto::Block),
 }
 
 impl MessageContent {
@@ -48,11 +61,17 @@ impl From<proto::Block> for MessageContent {
     }
 }
 
+#[cfg(feature = "seabird-client")]
+use crate::info::{BackendInfo, ChannelInfo, CoreInfo, InfoCache, UserInfo};
 #[cfg(feature = "seabird-client")]
 use crate::proto::seabird::seabird_client::SeabirdClient as SeabirdProtoClient;
+#[cfg(feature = "seabird-client")]
+use crate::stream::EventStream;
 
 #[cfg(feature = "chat-ingest-client")]
 use crate::proto::seabird::chat_ingest_client::ChatIngestClient as ChatIngestProtoClient;
+#[cfg(feature = "chat-ingest-client")]
+use crate::stream::ChatRequestStream;
 
 /// Configuration for connecting to a seabird instance.
 ///
@@ -85,8 +104,9 @@ pub type InnerClient =
 ///
 /// Most users should not need to use this directly. This interceptor
 /// automatically adds the "authorization" header with a Bearer token to every
-/// outgoing request.
-#[derive(Debug)]
+/// outgoing request. With the `opentelemetry` feature enabled, it also adds
+/// the context of the current span using the global OpenTelemetry propagator.
+#[derive(Clone, Debug)]
 pub struct AuthHeaderInterceptor {
     auth_header: MetadataValue<Ascii>,
 }
@@ -98,6 +118,7 @@ impl tonic::service::Interceptor for AuthHeaderInterceptor {
     ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
         req.metadata_mut()
             .insert("authorization", self.auth_header.clone());
+        telemetry::inject_trace_context(req.metadata_mut());
         Ok(req)
     }
 }
@@ -108,6 +129,9 @@ impl tonic::service::Interceptor for AuthHeaderInterceptor {
 /// channels and private conversations. It requires the `seabird-client` feature
 /// to be enabled.
 ///
+/// Cloning a client is cheap: clones share the same connection, so a clone can
+/// be handed to each task which needs to send messages.
+///
 /// # Examples
 ///
 /// ```rust,no_run
@@ -127,9 +151,10 @@ impl tonic::service::Interceptor for AuthHeaderInterceptor {
 /// }
 /// ```
 #[cfg(feature = "seabird-client")]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SeabirdClient {
     inner: SeabirdProtoClient<InnerClient>,
+    cache: Option<InfoCache>,
 }
 
 #[cfg(feature = "seabird-client")]
@@ -188,9 +213,64 @@ impl SeabirdClient {
 
         Ok(Self {
             inner: seabird_client,
+            cache: None,
         })
     }
 
+    /// Caches the results of info lookups, such as
+    /// [`channel_info`](Self::channel_info), for the given time.
+    ///
+    /// The cache is shared with any clones of this client, so it should be
+    /// enabled before cloning.
+    ///
+    /// The cache is also shared with event streams opened by this client, which
+    /// invalidate entries when an event shows they're out of date, such as a
+    /// message from a channel missing from a cached channel list. The
+    /// protocol has no join, part or topic change events though, so other
+    /// changes can go unnoticed for up to `ttl`. Channels changed through
+    /// this client are invalidated right away, and anything else can be
+    /// invalidated by hand with [`invalidate_channel_info`](Self::invalidate_channel_info)
+    /// or [`clear_info_cache`](Self::clear_info_cache).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use seabird::{ClientConfig, SeabirdClient};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let config = ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # };
+    /// let client = SeabirdClient::new(config)
+    ///     .await?
+    ///     .with_info_cache(Duration::from_secs(60));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_info_cache(mut self, ttl: std::time::Duration) -> Self {
+        self.cache = Some(InfoCache::new(ttl));
+        self
+    }
+
+    /// Removes a channel from the info cache, along with any cached channel
+    /// lists.
+    pub fn invalidate_channel_info(&mut self, channel_id: &str) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_channel(channel_id);
+        }
+    }
+
+    /// Removes everything from the info cache.
+    pub fn clear_info_cache(&mut self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
     /// Performs an action in a private conversation with a user.
     ///
     /// Actions are typically displayed differently than regular messages (e.g., "/me waves").
@@ -219,22 +299,39 @@ impl SeabirdClient {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.perform_private_action",
+            skip_all,
+            fields(target_id, tag_keys, grpc.status)
+        )
+    )]
     pub async fn perform_private_action(
         &mut self,
         user_id: impl Into<String>,
         content: impl Into<MessageContent>,
         tags: Option<HashMap<String, String>>,
     ) -> Result<()> {
+        let user_id = user_id.into();
+        let tags = tags.unwrap_or_default();
+        telemetry::record_target(&user_id, &tags);
+
         let (text, root_block) = content.into().into_inner();
 
-        self.inner
+        let started = Instant::now();
+        let resp = self
+            .inner
             .perform_private_action(proto::PerformPrivateActionRequest {
-                user_id: user_id.into(),
+                user_id,
                 text,
                 root_block,
-                tags: tags.unwrap_or_default(),
+                tags,
             })
-            .await?;
+            .await;
+        telemetry::record_response("seabird", "perform_private_action", started, &resp);
+
+        resp?;
         Ok(())
     }
 
@@ -266,22 +363,39 @@ impl SeabirdClient {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.perform_action",
+            skip_all,
+            fields(target_id, tag_keys, grpc.status)
+        )
+    )]
     pub async fn perform_action(
         &mut self,
         channel_id: impl Into<String>,
         content: impl Into<MessageContent>,
         tags: Option<HashMap<String, String>>,
     ) -> Result<()> {
+        let channel_id = channel_id.into();
+        let tags = tags.unwrap_or_default();
+        telemetry::record_target(&channel_id, &tags);
+
         let (text, root_block) = content.into().into_inner();
 
-        self.inner
+        let started = Instant::now();
+        let resp = self
+            .inner
             .perform_action(proto::PerformActionRequest {
-                channel_id: channel_id.into(),
+                channel_id,
                 text,
                 root_block,
-                tags: tags.unwrap_or_default(),
+                tags,
             })
-            .await?;
+            .await;
+        telemetry::record_response("seabird", "perform_action", started, &resp);
+
+        resp?;
         Ok(())
     }
 
@@ -316,22 +430,39 @@ impl SeabirdClient {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.send_message",
+            skip_all,
+            fields(target_id, tag_keys, grpc.status)
+        )
+    )]
     pub async fn send_message(
         &mut self,
         channel_id: impl Into<String>,
         content: impl Into<MessageContent>,
         tags: Option<HashMap<String, String>>,
     ) -> Result<()> {
+        let channel_id = channel_id.into();
+        let tags = tags.unwrap_or_default();
+        telemetry::record_target(&channel_id, &tags);
+
         let (text, root_block) = content.into().into_inner();
 
-        self.inner
+        let started = Instant::now();
+        let resp = self
+            .inner
             .send_message(proto::SendMessageRequest {
-                channel_id: channel_id.into(),
+                channel_id,
                 text,
                 root_block,
-                tags: tags.unwrap_or_default(),
+                tags,
             })
-            .await?;
+            .await;
+        telemetry::record_response("seabird", "send_message", started, &resp);
+
+        resp?;
         Ok(())
     }
 
@@ -361,25 +492,654 @@ impl SeabirdClient {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.send_private_message",
+            skip_all,
+            fields(target_id, tag_keys, grpc.status)
+        )
+    )]
     pub async fn send_private_message(
         &mut self,
         user_id: impl Into<String>,
         content: impl Into<MessageContent>,
         tags: Option<HashMap<String, String>>,
     ) -> Result<()> {
+        let user_id = user_id.into();
+        let tags = tags.unwrap_or_default();
+        telemetry::record_target(&user_id, &tags);
+
         let (text, root_block) = content.into().into_inner();
 
-        self.inner
+        let started = Instant::now();
+        let resp = self
+            .inner
             .send_private_message(proto::SendPrivateMessageRequest {
-                user_id: user_id.into(),
+                user_id,
                 text,
                 root_block,
-                tags: tags.unwrap_or_default(),
+                tags,
+            })
+            .await;
+        telemetry::record_response("seabird", "send_private_message", started, &resp);
+
+        resp?;
+        Ok(())
+    }
+
+    /// Starts building a message to a channel, which is sent with
+    /// [`SendRequest::send`].
+    ///
+    /// This is an alternative to [`send_message`](Self::send_message) which
+    /// doesn't need tags to be passed when there aren't any.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// client.message("channel-id", "Hello!").send().await?;
+    ///
+    /// client
+    ///     .message("channel-id", "Deploy finished")
+    ///     .thread_id("thread-id")
+    ///     .silent()
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn message(
+        &mut self,
+        channel_id: impl Into<String>,
+        content: impl Into<MessageContent>,
+    ) -> SendRequest<'_> {
+        SendRequest::new(self, SendKind::Message, channel_id.into(), content.into())
+    }
+
+    /// Starts building a private message to a user. See
+    /// [`message`](Self::message).
+    pub fn private_message(
+        &mut self,
+        user_id: impl Into<String>,
+        content: impl Into<MessageContent>,
+    ) -> SendRequest<'_> {
+        SendRequest::new(
+            self,
+            SendKind::PrivateMessage,
+            user_id.into(),
+            content.into(),
+        )
+    }
+
+    /// Starts building an action in a channel. See [`message`](Self::message).
+    pub fn action(
+        &mut self,
+        channel_id: impl Into<String>,
+        content: impl Into<MessageContent>,
+    ) -> SendRequest<'_> {
+        SendRequest::new(self, SendKind::Action, channel_id.into(), content.into())
+    }
+
+    /// Starts building a private action to a user. See
+    /// [`message`](Self::message).
+    pub fn private_action(
+        &mut self,
+        user_id: impl Into<String>,
+        content: impl Into<MessageContent>,
+    ) -> SendRequest<'_> {
+        SendRequest::new(
+            self,
+            SendKind::PrivateAction,
+            user_id.into(),
+            content.into(),
+        )
+    }
+
+    /// Opens a stream of events from the seabird instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - Metadata for the commands this bot handles, keyed by command name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// let mut events = client.stream_events(Default::default()).await?;
+    /// while let Some(event) = events.next().await? {
+    ///     println!("{:?}", event);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.stream_events",
+            skip_all,
+            fields(commands = commands.len(), grpc.status)
+        )
+    )]
+    pub async fn stream_events(
+        &mut self,
+        commands: HashMap<String, proto::CommandMetadata>,
+    ) -> Result<EventStream> {
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .stream_events(proto::StreamEventsRequest { commands })
+            .await;
+        telemetry::record_response("seabird", "stream_events", started, &resp);
+
+        Ok(EventStream::new(resp?.into_inner(), self.cache.clone()))
+    }
+
+    /// Joins a channel on a backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend_id` - The ID of the backend the channel is on
+    /// * `channel_name` - The name of the channel, in the backend's format
+    ///   (e.g. "#rust" on IRC)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// client.join_channel("backend-id", "#rust").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.join_channel",
+            skip_all,
+            fields(target_id, grpc.status)
+        )
+    )]
+    pub async fn join_channel(
+        &mut self,
+        backend_id: impl Into<String>,
+        channel_name: impl Into<String>,
+    ) -> Result<()> {
+        let backend_id = backend_id.into();
+        telemetry::record_target_id(&backend_id);
+
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .join_channel(proto::JoinChannelRequest {
+                backend_id,
+                channel_name: channel_name.into(),
             })
-            .await?;
+            .await;
+        telemetry::record_response("seabird", "join_channel", started, &resp);
+
+        resp?;
+        if let Some(cache) = &self.cache {
+            cache.invalidate_channel_lists();
+        }
         Ok(())
     }
 
+    /// Leaves a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel_id` - The ID of the channel to leave
+    /// * `message` - The message to leave with, for backends which support
+    ///   one. This can be empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// client.leave_channel("channel-id", "Goodbye!").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.leave_channel",
+            skip_all,
+            fields(target_id, grpc.status)
+        )
+    )]
+    pub async fn leave_channel(
+        &mut self,
+        channel_id: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Result<()> {
+        let channel_id = channel_id.into();
+        telemetry::record_target_id(&channel_id);
+
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .leave_channel(proto::LeaveChannelRequest {
+                channel_id: channel_id.clone(),
+                message: message.into(),
+            })
+            .await;
+        telemetry::record_response("seabird", "leave_channel", started, &resp);
+
+        resp?;
+        self.invalidate_channel_info(&channel_id);
+        Ok(())
+    }
+
+    /// Sets the topic of a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel_id` - The ID of the channel to update
+    /// * `topic` - The new topic
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails, such as when the bot isn't
+    /// allowed to change the topic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// client.update_channel_info("channel-id", "Release day!").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.update_channel_info",
+            skip_all,
+            fields(target_id, grpc.status)
+        )
+    )]
+    pub async fn update_channel_info(
+        &mut self,
+        channel_id: impl Into<String>,
+        topic: impl Into<String>,
+    ) -> Result<()> {
+        let channel_id = channel_id.into();
+        telemetry::record_target_id(&channel_id);
+
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .update_channel_info(proto::UpdateChannelInfoRequest {
+                channel_id: channel_id.clone(),
+                topic: topic.into(),
+            })
+            .await;
+        telemetry::record_response("seabird", "update_channel_info", started, &resp);
+
+        resp?;
+        self.invalidate_channel_info(&channel_id);
+        Ok(())
+    }
+
+    /// Looks up a channel by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails, such as when the channel
+    /// doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// let channel = client.channel_info("channel-id").await?;
+    /// println!("{}: {}", channel.display_name, channel.topic);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.channel_info",
+            skip_all,
+            fields(target_id, grpc.status)
+        )
+    )]
+    pub async fn channel_info(&mut self, channel_id: impl Into<String>) -> Result<ChannelInfo> {
+        let channel_id = channel_id.into();
+        telemetry::record_target_id(&channel_id);
+
+        if let Some(channel) = self.cache.as_ref().and_then(|c| c.channel(&channel_id)) {
+            return Ok(channel);
+        }
+
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .get_channel_info(proto::ChannelInfoRequest { channel_id })
+            .await;
+        telemetry::record_response("seabird", "channel_info", started, &resp);
+
+        let channel: ChannelInfo = resp?
+            .into_inner()
+            .channel
+            .context("seabird returned no channel")?
+            .into();
+        if let Some(cache) = &self.cache {
+            cache.insert_channel(channel.clone());
+        }
+        Ok(channel)
+    }
+
+    /// Lists the channels the bot is in on a backend.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.list_channels",
+            skip_all,
+            fields(target_id, grpc.status)
+        )
+    )]
+    pub async fn list_channels(
+        &mut self,
+        backend_id: impl Into<String>,
+    ) -> Result<Vec<ChannelInfo>> {
+        let backend_id = backend_id.into();
+        telemetry::record_target_id(&backend_id);
+
+        if let Some(channels) = self
+            .cache
+            .as_ref()
+            .and_then(|c| c.channel_list(&backend_id))
+        {
+            return Ok(channels);
+        }
+
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .list_channels(proto::ListChannelsRequest {
+                backend_id: backend_id.clone(),
+            })
+            .await;
+        telemetry::record_response("seabird", "list_channels", started, &resp);
+
+        let channels: Vec<ChannelInfo> = resp?
+            .into_inner()
+            .channels
+            .into_iter()
+            .map(ChannelInfo::from)
+            .collect();
+        if let Some(cache) = &self.cache {
+            cache.insert_channel_list(backend_id, channels.clone());
+        }
+        Ok(channels)
+    }
+
+    /// Looks up the user the bot is connected as on a backend.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.current_user",
+            skip_all,
+            fields(target_id, grpc.status)
+        )
+    )]
+    pub async fn current_user(&mut self, backend_id: impl Into<String>) -> Result<UserInfo> {
+        let backend_id = backend_id.into();
+        telemetry::record_target_id(&backend_id);
+
+        if let Some(user) = self
+            .cache
+            .as_ref()
+            .and_then(|c| c.current_user(&backend_id))
+        {
+            return Ok(user);
+        }
+
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .get_current_user(proto::GetCurrentUserRequest {
+                backend_id: backend_id.clone(),
+            })
+            .await;
+        telemetry::record_response("seabird", "current_user", started, &resp);
+
+        let user: UserInfo = resp?
+            .into_inner()
+            .user
+            .context("seabird returned no user")?
+            .into();
+        if let Some(cache) = &self.cache {
+            cache.insert_current_user(backend_id, user.clone());
+        }
+        Ok(user)
+    }
+
+    /// Looks up a backend by ID, including any metadata it reports.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails, such as when the backend
+    /// doesn't exist.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "seabird.backend_info",
+            skip_all,
+            fields(target_id, grpc.status)
+        )
+    )]
+    pub async fn backend_info(&mut self, backend_id: impl Into<String>) -> Result<BackendInfo> {
+        let backend_id = backend_id.into();
+        telemetry::record_target_id(&backend_id);
+
+        if let Some(backend) = self.cache.as_ref().and_then(|c| c.backend(&backend_id)) {
+            return Ok(backend);
+        }
+
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .get_backend_info(proto::BackendInfoRequest { backend_id })
+            .await;
+        telemetry::record_response("seabird", "backend_info", started, &resp);
+
+        let resp = resp?.into_inner();
+        let backend = resp.backend.context("seabird returned no backend")?;
+        let backend = BackendInfo {
+            id: backend.id,
+            backend_type: backend.r#type,
+            metadata: resp.metadata,
+        };
+        if let Some(cache) = &self.cache {
+            cache.insert_backend(backend.clone());
+        }
+        Ok(backend)
+    }
+
+    /// Lists the chat backends connected to the core, along with their
+    /// metadata.
+    ///
+    /// The core only lists the ID and type of each backend, so the metadata
+    /// is looked up with [`backend_info`](Self::backend_info), which uses the
+    /// info cache if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// for backend in client.list_backends().await? {
+    ///     println!("{} ({})", backend.id, backend.backend_type);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "seabird.list_backends", skip_all, fields(grpc.status))
+    )]
+    pub async fn list_backends(&mut self) -> Result<Vec<BackendInfo>> {
+        let started = Instant::now();
+        let resp = self
+            .inner
+            .list_backends(proto::ListBackendsRequest {})
+            .await;
+        telemetry::record_response("seabird", "list_backends", started, &resp);
+
+        let mut backends = Vec::new();
+        for backend in resp?.into_inner().backends {
+            backends.push(self.backend_info(backend.id).await?);
+        }
+        Ok(backends)
+    }
+
+    /// Looks up information about the seabird core, such as when it was
+    /// started.
+    ///
+    /// The core doesn't report its version through this RPC, so
+    /// [`CoreInfo`] doesn't include one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "seabird.core_info", skip_all, fields(grpc.status))
+    )]
+    pub async fn core_info(&mut self) -> Result<CoreInfo> {
+        let started = Instant::now();
+        let resp = self.inner.get_core_info(proto::CoreInfoRequest {}).await;
+        telemetry::record_response("seabird", "core_info", started, &resp);
+
+        Ok(resp?.into_inner().into())
+    }
+
+    /// Checks that the core is reachable and accepts the auth token, giving up
+    /// after the given timeout.
+    ///
+    /// On success this returns the same information as
+    /// [`core_info`](Self::core_info).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the core can't be reached in time, if the auth
+    /// token is rejected, or if the request fails for any other reason.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// # use seabird::{ClientConfig, SeabirdClient};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = SeabirdClient::new(ClientConfig {
+    /// #     url: "https://example.com".to_string(),
+    /// #     token: "token".to_string(),
+    /// # }).await?;
+    /// let info = client.health_check(Duration::from_secs(5)).await?;
+    /// println!("seabird has been up for {:?}", info.uptime());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "seabird.health_check", skip_all, fields(grpc.status))
+    )]
+    pub async fn health_check(&mut self, timeout: std::time::Duration) -> Result<CoreInfo> {
+        let mut request = tonic::Request::new(proto::CoreInfoRequest {});
+        request.set_timeout(timeout);
+
+        let started = Instant::now();
+        let resp = self.inner.get_core_info(request).await;
+        telemetry::record_response("seabird", "health_check", started, &resp);
+
+        match resp {
+            Ok(resp) => Ok(resp.into_inner().into()),
+            Err(status) => {
+                let message = match status.code() {
+                    tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => {
+                        "seabird rejected the auth token"
+                    }
+                    tonic::Code::Unavailable | tonic::Code::DeadlineExceeded => {
+                        "seabird is unreachable"
+                    }
+                    _ => "seabird health check failed",
+                };
+                Err(anyhow::Error::new(status).context(message))
+            }
+        }
+    }
+
     /// Returns a reference to the inner gRPC client.
     ///
     /// This provides access to the underlying tonic-generated client for
@@ -397,6 +1157,118 @@ impl SeabirdClient {
     }
 }
 
+/// What a [`SendRequest`] sends.
+#[cfg(feature = "seabird-client")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SendKind {
+    Message,
+    PrivateMessage,
+    Action,
+    PrivateAction,
+}
+
+/// A message or action being built, created by [`SeabirdClient::message`],
+/// [`private_message`](SeabirdClient::private_message),
+/// [`action`](SeabirdClient::action) or
+/// [`private_action`](SeabirdClient::private_action).
+///
+/// Nothing is sent until [`send`](Self::send) is awaited.
+#[cfg(feature = "seabird-client")]
+#[derive(Debug)]
+#[must_use = "nothing is sent until `send` is awaited"]
+pub struct SendRequest<'a> {
+    client: &'a mut SeabirdClient,
+    kind: SendKind,
+    target_id: String,
+    content: MessageContent,
+    tags: Tags,
+}
+
+#[cfg(feature = "seabird-client")]
+impl<'a> SendRequest<'a> {
+    fn new(
+        client: &'a mut SeabirdClient,
+        kind: SendKind,
+        target_id: String,
+        content: MessageContent,
+    ) -> Self {
+        Self {
+            client,
+            kind,
+            target_id,
+            content,
+            tags: Tags::new(),
+        }
+    }
+
+    /// Replaces the tags sent with the message.
+    pub fn tags(mut self, tags: impl Into<Tags>) -> Self {
+        self.tags = tags.into();
+        self
+    }
+
+    /// Sets a tag, replacing any existing value.
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags = self.tags.tag(key, value);
+        self
+    }
+
+    /// Marks the message as a reply to another message. See
+    /// [`tags::REPLY_TO`](crate::tags::REPLY_TO).
+    pub fn reply_to(mut self, message_id: impl Into<String>) -> Self {
+        self.tags = self.tags.reply_to(message_id);
+        self
+    }
+
+    /// Sends the message in a thread. See
+    /// [`tags::THREAD_ID`](crate::tags::THREAD_ID).
+    pub fn thread_id(mut self, thread_id: impl Into<String>) -> Self {
+        self.tags = self.tags.thread_id(thread_id);
+        self
+    }
+
+    /// Sends the message without notifying anyone. See
+    /// [`tags::SILENT`](crate::tags::SILENT).
+    pub fn silent(mut self) -> Self {
+        self.tags = self.tags.silent();
+        self
+    }
+
+    /// Makes sending the message idempotent. See
+    /// [`tags::IDEMPOTENCY_KEY`](crate::tags::IDEMPOTENCY_KEY).
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.tags = self.tags.idempotency_key(key);
+        self
+    }
+
+    /// Sends the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    pub async fn send(self) -> Result<()> {
+        let Self {
+            client,
+            kind,
+            target_id,
+            content,
+            tags,
+        } = self;
+        let tags = Some(tags.into());
+
+        match kind {
+            SendKind::Message => client.send_message(target_id, content, tags).await,
+            SendKind::PrivateMessage => client.send_private_message(target_id, content, tags).await,
+            SendKind::Action => client.perform_action(target_id, content, tags).await,
+            SendKind::PrivateAction => {
+                client
+                    .perform_private_action(target_id, content, tags)
+                    .await
+            }
+        }
+    }
+}
+
 /// Client for ingesting chat data into seabird.
 ///
 /// This client is used to send chat data from external sources into the seabird
@@ -421,7 +1293,7 @@ impl SeabirdClient {
 /// }
 /// ```
 #[cfg(feature = "chat-ingest-client")]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ChatIngestClient {
     inner: ChatIngestProtoClient<InnerClient>,
 }
@@ -485,6 +1357,34 @@ impl ChatIngestClient {
         })
     }
 
+    /// Opens the bidirectional chat ingest stream.
+    ///
+    /// Events from the chat backend are read from `events` and sent to the
+    /// seabird instance, while requests from the seabird instance (such as
+    /// messages to send) are returned as a [`ChatRequestStream`].
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - A stream of events from the chat backend
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gRPC request fails.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "seabird.ingest_events", skip_all, fields(grpc.status))
+    )]
+    pub async fn ingest_events(
+        &mut self,
+        events: impl tonic::IntoStreamingRequest<Message = proto::ChatEvent>,
+    ) -> Result<ChatRequestStream> {
+        let started = Instant::now();
+        let resp = self.inner.ingest_events(events).await;
+        telemetry::record_response("chat_ingest", "ingest_events", started, &resp);
+
+        Ok(ChatRequestStream::new(resp?.into_inner()))
+    }
+
     /// Returns a reference to the inner gRPC client.
     ///
     /// This provides access to the underlying tonic-generated client for
@@ -501,3 +1401,91 @@ impl ChatIngestClient {
         &mut self.inner
     }
 }
+
+#[cfg(all(test, feature = "seabird-client"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::tags;
+    use crate::testing::MockSeabird;
+    use crate::Block;
+
+    #[tokio::test]
+    async fn request_builders_send_their_tags() {
+        let (mock, mut client) = MockSeabird::start().await;
+
+        client.message("chan", "plain").send().await.unwrap();
+        client
+            .message("chan", Block::new().bold("blocks"))
+            .reply_to("message-1")
+            .thread_id("thread-1")
+            .silent()
+            .idempotency_key("key-1")
+            .tag("custom", "value")
+            .send()
+            .await
+            .unwrap();
+
+        let messages = mock.messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].text, "plain");
+        assert!(messages[0].tags.is_empty());
+        assert_eq!(messages[1].text, "blocks");
+        assert_eq!(
+            messages[1].tags,
+            HashMap::from(
+                [
+                    (tags::REPLY_TO, "message-1"),
+                    (tags::THREAD_ID, "thread-1"),
+                    (tags::SILENT, "true"),
+                    (tags::IDEMPOTENCY_KEY, "key-1"),
+                    ("custom", "value"),
+                ]
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn request_builders_send_to_the_right_place() {
+        let (mock, mut client) = MockSeabird::start().await;
+
+        client
+            .private_message("user-1", "private")
+            .tags(tags::Tags::new().silent())
+            .send()
+            .await
+            .unwrap();
+        client.action("chan", "waves").send().await.unwrap();
+        client
+            .private_action("user-1", "nods")
+            .send()
+            .await
+            .unwrap();
+
+        let sent = [
+            mock.messages(),
+            mock.private_messages(),
+            mock.actions(),
+            mock.private_actions(),
+        ]
+        .map(|sent| {
+            sent.into_iter()
+                .map(|sent| format!("{} {}", sent.target_id, sent.text))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            sent,
+            [
+                vec![],
+                vec!["user-1 private".to_string()],
+                vec!["chan waves".to_string()],
+                vec!["user-1 nods".to_string()],
+            ]
+        );
+        assert_eq!(
+            mock.private_messages()[0].tags.get(tags::SILENT),
+            Some(&"true".to_string())
+        );
+    }
+}
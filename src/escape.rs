@@ -0,0 +1,172 @@
+//! Escaping of untrusted text for each chat dialect.
+//!
+//! These functions make text display literally when it's sent to a backend
+//! which interprets formatting, such as Markdown on Discord or control codes
+//! on IRC. The [`render`](crate::render) module applies them to every text
+//! block, so they're only needed when building message text by hand.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use seabird::escape;
+//!
+//! assert_eq!(escape::markdown("*not italic*"), "\\*not italic\\*");
+//! assert_eq!(escape::irc("\x02not bold\x02"), "not bold");
+//! ```
+
+use crate::render::Dialect;
+
+/// Escapes text for the given dialect.
+pub fn escape(text: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Plain => text.to_string(),
+        Dialect::Markdown => markdown(text),
+        Dialect::Discord => discord(text),
+        Dialect::Irc => irc(text),
+    }
+}
+
+/// Escapes text so it displays literally in CommonMark-style Markdown.
+///
+/// Inline formatting characters are always escaped with a backslash, while
+/// characters which only have meaning at the start of a line (such as `#` for
+/// headings or `1.` for lists) are escaped when they appear there.
+pub fn markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            escaped.push('\n');
+        }
+        escape_markdown_line(line, &mut escaped);
+    }
+    escaped
+}
+
+/// Escapes text so it displays literally on Discord.
+///
+/// In addition to the escaping done by [`markdown`], this stops `@everyone`
+/// and `@here` from pinging the whole channel.
+pub fn discord(text: &str) -> String {
+    markdown(text)
+        .replace("@everyone", "@\u{200b}everyone")
+        .replace("@here", "@\u{200b}here")
+}
+
+/// Escapes a URL so it can't end a Markdown link early.
+///
+/// Characters which would close the link, or stop it being parsed as one, are
+/// percent-encoded.
+pub fn markdown_url(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            ' ' => escaped.push_str("%20"),
+            '(' => escaped.push_str("%28"),
+            ')' => escaped.push_str("%29"),
+            '<' => escaped.push_str("%3C"),
+            '>' => escaped.push_str("%3E"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Removes IRC formatting and other control codes from text.
+///
+/// Line breaks are kept, but carriage returns are removed so text can't
+/// inject extra lines into the IRC protocol.
+pub fn irc(text: &str) -> String {
+    text.chars()
+        .filter(|c| *c == '\n' || !c.is_control())
+        .collect()
+}
+
+fn escape_markdown_line(line: &str, escaped: &mut String) {
+    // Leading whitespace doesn't stop a line from being a heading, list or
+    // quote, so it's kept as is before checking the start of the line.
+    let content = line.trim_start_matches([' ', '\t']);
+    escaped.push_str(&line[..line.len() - content.len()]);
+
+    let mut rest = content;
+    let digits = content.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 && matches!(content.as_bytes().get(digits), Some(b'.' | b')')) {
+        // An ordered list marker, such as "1." or "1)".
+        escaped.push_str(&content[..digits]);
+        escaped.push('\\');
+        rest = &content[digits..];
+    } else if content.starts_with(['#', '-', '+', '=']) {
+        escaped.push('\\');
+    }
+
+    for c in rest.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '~' | '[' | ']' | '<' | '>' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Block;
+
+    #[test]
+    fn escapes_inline_markdown() {
+        assert_eq!(
+            markdown(r"a*b_c~d`e|f[g](h)<i>\j"),
+            r"a\*b\_c\~d\`e\|f\[g\](h)\<i\>\\j"
+        );
+    }
+
+    #[test]
+    fn escapes_markdown_line_starts() {
+        assert_eq!(
+            markdown("# title\n  - item\n10. step\n2) step\n+ more\nplain 1. text"),
+            "\\# title\n  \\- item\n10\\. step\n2\\) step\n\\+ more\nplain 1. text"
+        );
+    }
+
+    #[test]
+    fn discord_defuses_mass_mentions() {
+        assert_eq!(
+            discord("@everyone and @here *now*"),
+            "@\u{200b}everyone and @\u{200b}here \\*now\\*"
+        );
+    }
+
+    #[test]
+    fn irc_strips_control_codes() {
+        assert_eq!(irc("\x02bold\x02\x0304red\x03\r\nnext"), "bold04red\nnext");
+    }
+
+    #[test]
+    fn markdown_urls_cannot_close_links() {
+        assert_eq!(
+            markdown_url("https://example.com/a (b)<c>\n"),
+            "https://example.com/a%20%28b%29%3Cc%3E"
+        );
+    }
+
+    #[test]
+    fn rendering_escapes_text_but_not_code() {
+        let block = Block::new()
+            .text("*a*")
+            .inline_code("*b*")
+            .link("https://example.com/)", "[c]");
+
+        assert_eq!(
+            block.render(Dialect::Markdown),
+            r"\*a\*`*b*`[\[c\]](https://example.com/%29)"
+        );
+        assert_eq!(
+            block.render(Dialect::Plain),
+            "*a**b*[c] (https://example.com/))"
+        );
+        assert_eq!(Block::new().text("\x02x\x0f").render(Dialect::Irc), "x");
+    }
+}
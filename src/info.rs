@@ -0,0 +1,417 @@
+//! Information about channels, users, backends and the seabird core.
+//!
+//! These types are returned by the lookup methods on
+//! [`SeabirdClient`](crate::SeabirdClient), such as
+//! [`channel_info`](crate::SeabirdClient::channel_info). Lookups can
+//! optionally be cached with
+//! [`with_info_cache`](crate::SeabirdClient::with_info_cache).
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::proto;
+
+/// A channel on a chat backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelInfo {
+    /// The ID of the channel, used to send messages to it.
+    pub id: String,
+    /// The name of the channel as shown to users.
+    pub display_name: String,
+    /// The channel topic, which is empty if there isn't one.
+    pub topic: String,
+}
+
+impl From<proto::Channel> for ChannelInfo {
+    fn from(channel: proto::Channel) -> Self {
+        Self {
+            id: channel.id,
+            display_name: channel.display_name,
+            topic: channel.topic,
+        }
+    }
+}
+
+/// A user on a chat backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserInfo {
+    /// The ID of the user, used to send private messages to them.
+    pub id: String,
+    /// The name of the user as shown to other users.
+    pub display_name: String,
+}
+
+impl From<proto::User> for UserInfo {
+    fn from(user: proto::User) -> Self {
+        Self {
+            id: user.id,
+            display_name: user.display_name,
+        }
+    }
+}
+
+/// A chat backend connected to the seabird core.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendInfo {
+    /// The ID of the backend.
+    pub id: String,
+    /// The type of chat service the backend connects to, such as `irc` or
+    /// `discord`.
+    pub backend_type: String,
+    /// Any extra information the backend reports about itself.
+    pub metadata: HashMap<String, String>,
+}
+
+/// Information about the seabird core.
+///
+/// The core info RPC doesn't report a version, so there's no way to tell
+/// which version of the core is running from here.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoreInfo {
+    /// The names of the services currently connected to the core.
+    pub current_services: Vec<String>,
+    /// When the core was started.
+    pub startup_time: SystemTime,
+}
+
+impl CoreInfo {
+    /// Returns how long the core has been running.
+    pub fn uptime(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.startup_time)
+            .unwrap_or_default()
+    }
+}
+
+impl From<proto::CoreInfoResponse> for CoreInfo {
+    fn from(info: proto::CoreInfoResponse) -> Self {
+        Self {
+            current_services: info.current_services,
+            startup_time: UNIX_EPOCH + Duration::from_secs(info.startup_timestamp),
+        }
+    }
+}
+
+/// A map whose entries expire a fixed time after they were inserted.
+#[derive(Debug)]
+struct TtlMap<K, V> {
+    entries: HashMap<K, (Instant, V)>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlMap<K, V> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get<Q: Eq + Hash + ?Sized>(&self, key: &Q, ttl: Duration) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.entries
+            .get(key)
+            .filter(|(inserted, _)| inserted.elapsed() < ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.entries.insert(key, (Instant::now(), value));
+    }
+
+    fn remove<Q: Eq + Hash + ?Sized>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+    {
+        self.entries.remove(key);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Cached results of the info lookups on a client.
+///
+/// The cache is shared between a client and the event streams it opens, so
+/// events can invalidate entries as they're received. The protocol has no
+/// join, part or topic change events, so the stream can only invalidate what
+/// the events it does have reveal:
+///
+/// - an event from a channel which isn't in any cached channel list means the
+///   bot has joined it since, so every channel list is dropped
+/// - an event from the bot's own user with a different display name means
+///   it has been renamed, so the cached current user is dropped
+///
+/// Anything else is invalidated when it expires, when the client itself
+/// changes a channel, or by hand.
+#[derive(Clone, Debug)]
+pub(crate) struct InfoCache {
+    ttl: Duration,
+    entries: Arc<Mutex<Entries>>,
+}
+
+#[derive(Debug)]
+struct Entries {
+    channels: TtlMap<String, ChannelInfo>,
+    channel_lists: TtlMap<String, Vec<ChannelInfo>>,
+    current_users: TtlMap<String, UserInfo>,
+    backends: TtlMap<String, BackendInfo>,
+}
+
+impl InfoCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(Entries {
+                channels: TtlMap::new(),
+                channel_lists: TtlMap::new(),
+                current_users: TtlMap::new(),
+                backends: TtlMap::new(),
+            })),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        // Nothing can panic while the lock is held, so the entries are never
+        // left half updated.
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn channel(&self, channel_id: &str) -> Option<ChannelInfo> {
+        self.entries().channels.get(channel_id, self.ttl)
+    }
+
+    pub(crate) fn insert_channel(&self, channel: ChannelInfo) {
+        self.entries().channels.insert(channel.id.clone(), channel);
+    }
+
+    pub(crate) fn channel_list(&self, backend_id: &str) -> Option<Vec<ChannelInfo>> {
+        self.entries().channel_lists.get(backend_id, self.ttl)
+    }
+
+    pub(crate) fn insert_channel_list(&self, backend_id: String, channels: Vec<ChannelInfo>) {
+        let mut entries = self.entries();
+        for channel in &channels {
+            entries.channels.insert(channel.id.clone(), channel.clone());
+        }
+        entries.channel_lists.insert(backend_id, channels);
+    }
+
+    pub(crate) fn current_user(&self, backend_id: &str) -> Option<UserInfo> {
+        self.entries().current_users.get(backend_id, self.ttl)
+    }
+
+    pub(crate) fn insert_current_user(&self, backend_id: String, user: UserInfo) {
+        self.entries().current_users.insert(backend_id, user);
+    }
+
+    pub(crate) fn backend(&self, backend_id: &str) -> Option<BackendInfo> {
+        self.entries().backends.get(backend_id, self.ttl)
+    }
+
+    pub(crate) fn insert_backend(&self, backend: BackendInfo) {
+        self.entries().backends.insert(backend.id.clone(), backend);
+    }
+
+    /// Removes a channel, along with every channel list since any of them
+    /// could include it.
+    pub(crate) fn invalidate_channel(&self, channel_id: &str) {
+        let mut entries = self.entries();
+        entries.channels.remove(channel_id);
+        entries.channel_lists.clear();
+    }
+
+    /// Removes every channel list, such as after joining a channel.
+    pub(crate) fn invalidate_channel_lists(&self) {
+        self.entries().channel_lists.clear();
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut entries = self.entries();
+        entries.channels.clear();
+        entries.channel_lists.clear();
+        entries.current_users.clear();
+        entries.backends.clear();
+    }
+
+    /// Invalidates anything an event received on the stream shows is out of
+    /// date.
+    pub(crate) fn observe(&self, event: &proto::Event) {
+        use proto::event::Inner;
+
+        let (channel_id, user) = match &event.inner {
+            Some(Inner::Message(proto::MessageEvent { source, .. }))
+            | Some(Inner::Mention(proto::MentionEvent { source, .. }))
+            | Some(Inner::Command(proto::CommandEvent { source, .. }))
+            | Some(Inner::Action(proto::ActionEvent { source, .. })) => match source {
+                Some(source) => (Some(&source.channel_id), source.user.as_ref()),
+                None => (None, None),
+            },
+            Some(Inner::PrivateMessage(proto::PrivateMessageEvent { source, .. }))
+            | Some(Inner::PrivateAction(proto::PrivateActionEvent { source, .. })) => {
+                (None, source.as_ref())
+            }
+            Some(Inner::SendMessage(proto::SendMessageEvent { channel_id, .. }))
+            | Some(Inner::PerformAction(proto::PerformActionEvent { channel_id, .. })) => {
+                (Some(channel_id), None)
+            }
+            Some(Inner::SendPrivateMessage(_)) | Some(Inner::PerformPrivateAction(_)) | None => {
+                (None, None)
+            }
+        };
+
+        let mut entries = self.entries();
+        if let Some(channel_id) = channel_id {
+            let lists = &entries.channel_lists.entries;
+            let listed = lists
+                .values()
+                .any(|(_, channels)| channels.iter().any(|channel| &channel.id == channel_id));
+            if !lists.is_empty() && !listed {
+                entries.channel_lists.clear();
+            }
+        }
+        if let Some(user) = user {
+            entries.current_users.entries.retain(|_, (_, current)| {
+                current.id != user.id || current.display_name == user.display_name
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn channel(id: &str) -> ChannelInfo {
+        ChannelInfo {
+            id: id.to_string(),
+            display_name: format!("#{id}"),
+            topic: String::new(),
+        }
+    }
+
+    fn user(id: &str, display_name: &str) -> proto::User {
+        proto::User {
+            id: id.to_string(),
+            display_name: display_name.to_string(),
+        }
+    }
+
+    fn message(channel_id: &str, user: proto::User) -> proto::Event {
+        proto::Event {
+            tags: HashMap::new(),
+            inner: Some(proto::event::Inner::Message(proto::MessageEvent {
+                source: Some(proto::ChannelSource {
+                    channel_id: channel_id.to_string(),
+                    user: Some(user),
+                }),
+                text: "hello".to_string(),
+                root_block: None,
+            })),
+        }
+    }
+
+    #[test]
+    fn entries_expire() {
+        let cache = InfoCache::new(Duration::ZERO);
+        cache.insert_channel(channel("a"));
+        assert_eq!(cache.channel("a"), None);
+
+        let cache = InfoCache::new(Duration::from_secs(60));
+        cache.insert_channel(channel("a"));
+        assert_eq!(cache.channel("a"), Some(channel("a")));
+        assert_eq!(cache.channel("b"), None);
+    }
+
+    #[test]
+    fn clones_share_entries() {
+        let cache = InfoCache::new(Duration::from_secs(60));
+        cache.clone().insert_channel(channel("a"));
+        assert_eq!(cache.channel("a"), Some(channel("a")));
+
+        cache.clone().clear();
+        assert_eq!(cache.channel("a"), None);
+    }
+
+    #[test]
+    fn channel_lists_fill_channels() {
+        let cache = InfoCache::new(Duration::from_secs(60));
+        cache.insert_channel_list("irc".to_string(), vec![channel("a"), channel("b")]);
+        assert_eq!(cache.channel("b"), Some(channel("b")));
+
+        cache.invalidate_channel("b");
+        assert_eq!(cache.channel("a"), Some(channel("a")));
+        assert_eq!(cache.channel("b"), None);
+        assert_eq!(cache.channel_list("irc"), None);
+    }
+
+    #[test]
+    fn events_from_unlisted_channels_drop_channel_lists() {
+        let cache = InfoCache::new(Duration::from_secs(60));
+        cache.insert_channel_list("irc".to_string(), vec![channel("a")]);
+
+        cache.observe(&message("a", user("u1", "alice")));
+        assert_eq!(cache.channel_list("irc"), Some(vec![channel("a")]));
+
+        cache.observe(&proto::Event {
+            tags: HashMap::new(),
+            inner: Some(proto::event::Inner::SendMessage(proto::SendMessageEvent {
+                sender: "other-plugin".to_string(),
+                channel_id: "b".to_string(),
+                text: "hello".to_string(),
+                root_block: None,
+            })),
+        });
+        assert_eq!(cache.channel_list("irc"), None);
+        assert_eq!(cache.channel("a"), Some(channel("a")));
+    }
+
+    #[test]
+    fn renames_drop_the_current_user() {
+        let cache = InfoCache::new(Duration::from_secs(60));
+        cache.insert_current_user("irc".to_string(), user("bot", "seabird").into());
+
+        cache.observe(&message("a", user("bot", "seabird")));
+        cache.observe(&message("a", user("u1", "alice")));
+        assert_eq!(
+            cache.current_user("irc"),
+            Some(UserInfo::from(user("bot", "seabird")))
+        );
+
+        cache.observe(&proto::Event {
+            tags: HashMap::new(),
+            inner: Some(proto::event::Inner::PrivateMessage(
+                proto::PrivateMessageEvent {
+                    source: Some(user("bot", "seabird2")),
+                    text: "hello".to_string(),
+                    root_block: None,
+                },
+            )),
+        });
+        assert_eq!(cache.current_user("irc"), None);
+    }
+
+    #[test]
+    fn core_info_reports_uptime() {
+        let started = SystemTime::now() - Duration::from_secs(90);
+        let info = CoreInfo::from(proto::CoreInfoResponse {
+            current_services: vec!["irc".to_string()],
+            startup_timestamp: started.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        });
+
+        assert_eq!(info.current_services, ["irc"]);
+        assert!((90..100).contains(&info.uptime().as_secs()));
+
+        let future = CoreInfo {
+            current_services: Vec::new(),
+            startup_time: SystemTime::now() + Duration::from_secs(60),
+        };
+        assert_eq!(future.uptime(), Duration::ZERO);
+    }
+}
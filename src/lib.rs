@@ -10,6 +10,22 @@
 //!
 //! - `seabird-client` (default): Enables the main SeabirdClient for bot interactions
 //! - `chat-ingest-client`: Enables the ChatIngestClient for ingesting chat data
+//! - `tracing`: Emits `tracing` spans for client RPCs and events received on
+//!   the event stream
+//! - `opentelemetry`: Propagates the context of the current span in request
+//!   metadata with the global OpenTelemetry propagator (implies `tracing`)
+//! - `metrics`: Records request, error and latency metrics for client RPCs,
+//!   along with stream and event counts, through the `metrics` facade
+//! - `serde`: Implements `Serialize` and `Deserialize` for `Block` and
+//!   `MessageContent`
+//! - `replay`: Records events from the event stream to a file and replays
+//!   them through the same `EventStream` interface
+//! - `bot`: Enables the `bot` module, which dispatches commands and filtered
+//!   events from the event stream to async handlers
+//! - `scheduler`: Enables the `scheduler` module, for sending messages at a
+//!   later time or on a cron schedule
+//! - `cli`: Builds the `seabird-cli` binary, for sending messages and watching
+//!   events from the command line
 //!
 //! # Example
 //!
@@ -31,15 +47,44 @@
 //! ```
 
 mod block;
+#[cfg(feature = "bot")]
+pub mod bot;
+mod civil;
 mod client;
 pub mod error;
+pub mod escape;
+#[cfg(feature = "seabird-client")]
+pub mod info;
 pub mod proto;
+pub mod render;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+#[cfg(feature = "serde")]
+mod serde;
+mod stream;
+pub mod tags;
+mod telemetry;
+pub mod template;
+#[cfg(all(test, feature = "seabird-client"))]
+mod testing;
+mod truncate;
+pub mod validate;
+pub mod visit;
 
 pub use block::Block;
-pub use client::{ClientConfig, InnerClient};
+pub use client::{ClientConfig, InnerClient, MessageContent};
+pub use tags::Tags;
+
+#[cfg(feature = "seabird-client")]
+pub use client::{SeabirdClient, SendRequest};
 
 #[cfg(feature = "seabird-client")]
-pub use client::SeabirdClient;
+pub use stream::EventStream;
 
 #[cfg(feature = "chat-ingest-client")]
 pub use client::ChatIngestClient;
+
+#[cfg(feature = "chat-ingest-client")]
+pub use stream::ChatRequestStream;
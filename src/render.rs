@@ -0,0 +1,638 @@
+//! Rendering of block trees to text.
+//!
+//! Chat backends each have their own formatting syntax. This module renders a
+//! block tree into the syntax of a given [`Dialect`], falling back to plain
+//! text for anything the dialect can't express.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use seabird::render::Dialect;
+//! use seabird::Block;
+//!
+//! let block = Block::new().text("Hello ").bold("world");
+//!
+//! assert_eq!(block.render(Dialect::Plain), "Hello world");
+//! assert_eq!(block.render(Dialect::Markdown), "Hello **world**");
+//! assert_eq!(block.render(Dialect::Irc), "Hello \x02world\x02");
+//! ```
+//!
+//! Text is escaped with the [`escape`](crate::escape) functions for the
+//! dialect, so formatting characters in text blocks are displayed literally.
+//!
+//! ```rust
+//! use seabird::render::Dialect;
+//! use seabird::Block;
+//!
+//! let block = Block::new().bold("*starred*");
+//!
+//! assert_eq!(block.render(Dialect::Markdown), "**\\*starred\\***");
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::block::{mention_user_id, Counts, Hints};
+use crate::civil::civil_from_days;
+use crate::escape;
+use crate::proto;
+use crate::proto::block::Inner;
+
+/// The formatting syntax to render blocks into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// Plain text without any formatting.
+    Plain,
+    /// CommonMark-style Markdown.
+    Markdown,
+    /// Discord's Markdown variant, with support for underline, spoilers,
+    /// timestamps and native user mentions.
+    Discord,
+    /// IRC control codes.
+    Irc,
+}
+
+/// How a timestamp should be displayed.
+///
+/// Dialects with native timestamps (such as Discord) display these in the
+/// reader's own time zone. Other dialects display them in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TimestampStyle {
+    /// Relative to the current time, such as "in 5 minutes" or "2 days ago".
+    Relative,
+    /// Only the date, such as "2024-01-31".
+    ShortDate,
+    /// The full date and time, such as "2024-01-31 17:30:00 UTC".
+    LongDateTime,
+    /// Only the time of day, such as "17:30 UTC".
+    Time,
+}
+
+/// Formats a timestamp in the given style, for dialects without native
+/// timestamps. Relative timestamps are formatted relative to `now`.
+pub(crate) fn format_timestamp(
+    timestamp: &prost_types::Timestamp,
+    style: TimestampStyle,
+    now: SystemTime,
+) -> String {
+    let days = timestamp.seconds.div_euclid(86400);
+    let secs_of_day = timestamp.seconds.rem_euclid(86400);
+    let (hour, minute, second) = (
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    );
+    let (year, month, day) = civil_from_days(days);
+
+    match style {
+        TimestampStyle::ShortDate => format!("{:04}-{:02}-{:02}", year, month, day),
+        TimestampStyle::LongDateTime => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            year, month, day, hour, minute, second
+        ),
+        TimestampStyle::Time => format!("{:02}:{:02} UTC", hour, minute),
+        TimestampStyle::Relative => {
+            let now = match now.duration_since(UNIX_EPOCH) {
+                Ok(duration) => duration.as_secs() as i64,
+                Err(err) => -(err.duration().as_secs() as i64),
+            };
+            format_relative(timestamp.seconds - now)
+        }
+    }
+}
+
+/// Formats an offset in seconds from now, such as "in 5 minutes".
+fn format_relative(offset: i64) -> String {
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 86400, "year"),
+        (30 * 86400, "month"),
+        (86400, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+        (1, "second"),
+    ];
+
+    let Some((count, unit)) = UNITS
+        .iter()
+        .map(|(secs, unit)| (offset.abs() / secs, unit))
+        .find(|(count, _)| *count > 0)
+    else {
+        return "now".to_string();
+    };
+
+    let plural = if count == 1 { "" } else { "s" };
+    if offset > 0 {
+        format!("in {} {}{}", count, unit, plural)
+    } else {
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
+
+/// Renders a block tree in the given dialect.
+///
+/// Plain blocks don't carry the numbering of ordered lists or the style of
+/// timestamps, so every list is rendered as a bulleted list and timestamps
+/// are rendered without a style. Use [`Block::render`](crate::Block::render)
+/// to render those.
+pub fn render(block: &proto::Block, dialect: Dialect) -> String {
+    let hints = Hints::default();
+    let mut renderer = Renderer::new(dialect, &hints);
+    renderer.block(block);
+    renderer.finish()
+}
+
+pub(crate) struct Renderer<'h> {
+    dialect: Dialect,
+    hints: &'h Hints,
+    /// The hinted blocks rendered so far, used to look up their hints.
+    seen: Counts,
+    out: String,
+}
+
+impl<'h> Renderer<'h> {
+    pub(crate) fn new(dialect: Dialect, hints: &'h Hints) -> Self {
+        Self {
+            dialect,
+            hints,
+            seen: Counts::default(),
+            out: String::new(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out.trim_end_matches('\n').to_string()
+    }
+
+    /// Renders a block in a separate renderer, for blocks which need to
+    /// post-process their contents line by line.
+    fn render_nested(&mut self, block: Option<&proto::Block>) -> String {
+        let mut renderer = Renderer::new(self.dialect, self.hints);
+        renderer.seen = self.seen;
+        if let Some(block) = block {
+            renderer.block(block);
+        }
+        self.seen = renderer.seen;
+        renderer.finish()
+    }
+
+    /// Makes sure the next output starts on its own line.
+    fn start_line(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn wrap(&mut self, open: &str, inner: Option<&proto::Block>, close: &str) {
+        self.out.push_str(open);
+        if let Some(inner) = inner {
+            self.block(inner);
+        }
+        self.out.push_str(close);
+    }
+
+    /// Renders a list, numbered from `start` if it's an ordered list.
+    ///
+    /// Continuation lines of each item, including any nested lists, are
+    /// indented to line up with the start of the item text.
+    fn list(&mut self, items: &[proto::Block], start: Option<u64>) {
+        self.start_line();
+        for (i, item) in items.iter().enumerate() {
+            let marker = match start {
+                Some(start) => format!("{}. ", start + i as u64),
+                None => "- ".to_string(),
+            };
+            let indent = " ".repeat(marker.len());
+
+            let item = self.render_nested(Some(item));
+            let mut lines = item.lines();
+            self.out.push_str(&marker);
+            self.out.push_str(lines.next().unwrap_or_default());
+            self.out.push('\n');
+            for line in lines {
+                if !line.is_empty() {
+                    self.out.push_str(&indent);
+                }
+                self.out.push_str(line);
+                self.out.push('\n');
+            }
+        }
+    }
+
+    pub(crate) fn block(&mut self, block: &proto::Block) {
+        let Some(inner) = &block.inner else {
+            return;
+        };
+
+        match (inner, self.dialect) {
+            (Inner::Text(text), dialect) => self.out.push_str(&escape::escape(&text.text, dialect)),
+            (Inner::Container(container), _) => {
+                for child in &container.inner {
+                    self.block(child);
+                }
+            }
+
+            (Inner::Bold(bold), Dialect::Markdown | Dialect::Discord) => {
+                self.wrap("**", bold.inner.as_deref(), "**")
+            }
+            (Inner::Bold(bold), Dialect::Irc) => self.wrap("\x02", bold.inner.as_deref(), "\x02"),
+            (Inner::Italics(italics), Dialect::Markdown | Dialect::Discord) => {
+                self.wrap("*", italics.inner.as_deref(), "*")
+            }
+            (Inner::Italics(italics), Dialect::Irc) => {
+                self.wrap("\x1d", italics.inner.as_deref(), "\x1d")
+            }
+            (Inner::Underline(underline), Dialect::Discord) => {
+                self.wrap("__", underline.inner.as_deref(), "__")
+            }
+            (Inner::Underline(underline), Dialect::Irc) => {
+                self.wrap("\x1f", underline.inner.as_deref(), "\x1f")
+            }
+            (Inner::Strikethrough(strikethrough), Dialect::Markdown | Dialect::Discord) => {
+                self.wrap("~~", strikethrough.inner.as_deref(), "~~")
+            }
+            (Inner::Strikethrough(strikethrough), Dialect::Irc) => {
+                self.wrap("\x1e", strikethrough.inner.as_deref(), "\x1e")
+            }
+            (Inner::Spoiler(spoiler), Dialect::Discord) => {
+                self.wrap("||", spoiler.inner.as_deref(), "||")
+            }
+            // IRC has no spoilers, so the closest thing is black on black text.
+            (Inner::Spoiler(spoiler), Dialect::Irc) => {
+                self.wrap("\x0301,01", spoiler.inner.as_deref(), "\x03")
+            }
+            // Anything else can't be expressed in the dialect, so only the
+            // contents are rendered.
+            (Inner::Bold(bold), _) => self.wrap("", bold.inner.as_deref(), ""),
+            (Inner::Italics(italics), _) => self.wrap("", italics.inner.as_deref(), ""),
+            (Inner::Underline(underline), _) => self.wrap("", underline.inner.as_deref(), ""),
+            (Inner::Strikethrough(strikethrough), _) => {
+                self.wrap("", strikethrough.inner.as_deref(), "")
+            }
+            (Inner::Spoiler(spoiler), _) => self.wrap("", spoiler.inner.as_deref(), ""),
+
+            (Inner::InlineCode(code), Dialect::Markdown | Dialect::Discord) => {
+                let fence = code_fence(&code.text, 1);
+                let padding = if code.text.starts_with('`') || code.text.ends_with('`') {
+                    " "
+                } else {
+                    ""
+                };
+                self.out.push_str(&fence);
+                self.out.push_str(padding);
+                self.out.push_str(&code.text);
+                self.out.push_str(padding);
+                self.out.push_str(&fence);
+            }
+            (Inner::InlineCode(code), Dialect::Irc) => {
+                self.out.push('\x11');
+                self.out.push_str(&escape::irc(&code.text));
+                self.out.push('\x11');
+            }
+            (Inner::InlineCode(code), Dialect::Plain) => self.out.push_str(&code.text),
+
+            (Inner::FencedCode(code), Dialect::Markdown | Dialect::Discord) => {
+                let fence = code_fence(&code.text, 3);
+                self.start_line();
+                self.out.push_str(&fence);
+                self.out.push_str(&code.info);
+                self.out.push('\n');
+                self.out.push_str(code.text.trim_end_matches('\n'));
+                self.out.push('\n');
+                self.out.push_str(&fence);
+                self.out.push('\n');
+            }
+            (Inner::FencedCode(code), Dialect::Plain) => {
+                self.start_line();
+                self.out.push_str(code.text.trim_end_matches('\n'));
+                self.out.push('\n');
+            }
+            (Inner::FencedCode(code), Dialect::Irc) => {
+                self.start_line();
+                self.out
+                    .push_str(&escape::irc(code.text.trim_end_matches('\n')));
+                self.out.push('\n');
+            }
+
+            (Inner::Blockquote(blockquote), _) => {
+                let inner = self.render_nested(blockquote.inner.as_deref());
+                self.start_line();
+                for line in inner.lines() {
+                    self.out.push_str("> ");
+                    self.out.push_str(line);
+                    self.out.push('\n');
+                }
+            }
+
+            (Inner::Link(link), dialect) => {
+                if let Some(user_id) = mention_user_id(link) {
+                    // The contents are only used for the display name, but
+                    // any hinted blocks in them still need to be counted.
+                    self.seen += Counts::of(link.inner.as_deref());
+                    let text = render(
+                        link.inner.as_deref().unwrap_or(&proto::Block::default()),
+                        Dialect::Plain,
+                    );
+                    let display_name = text.strip_prefix('@').unwrap_or(&text);
+                    match dialect {
+                        Dialect::Discord => {
+                            self.out.push_str("<@");
+                            self.out.push_str(user_id);
+                            self.out.push('>');
+                        }
+                        Dialect::Irc => self.out.push_str(&escape::irc(display_name)),
+                        Dialect::Plain | Dialect::Markdown => {
+                            self.out.push('@');
+                            self.out.push_str(&escape::escape(display_name, dialect));
+                        }
+                    }
+                    return;
+                }
+
+                match dialect {
+                    Dialect::Markdown | Dialect::Discord => {
+                        self.wrap("[", link.inner.as_deref(), "](");
+                        self.out.push_str(&escape::markdown_url(&link.url));
+                        self.out.push(')');
+                    }
+                    Dialect::Plain | Dialect::Irc => {
+                        let text = self.render_nested(link.inner.as_deref());
+                        let url = escape::escape(&link.url, dialect);
+                        if text.is_empty() || text == url {
+                            self.out.push_str(&url);
+                        } else {
+                            self.out.push_str(&text);
+                            self.out.push_str(" (");
+                            self.out.push_str(&url);
+                            self.out.push(')');
+                        }
+                    }
+                }
+            }
+
+            (Inner::Heading(heading), dialect) => {
+                self.start_line();
+                match dialect {
+                    Dialect::Markdown | Dialect::Discord => {
+                        // Discord only supports three levels of headings.
+                        let max_level = if dialect == Dialect::Discord { 3 } else { 6 };
+                        let level = heading.level.clamp(1, max_level) as usize;
+                        self.out.push_str(&"#".repeat(level));
+                        self.wrap(" ", heading.inner.as_deref(), "\n");
+                    }
+                    Dialect::Irc => self.wrap("\x02", heading.inner.as_deref(), "\x02\n"),
+                    Dialect::Plain => self.wrap("", heading.inner.as_deref(), "\n"),
+                }
+            }
+
+            (Inner::List(list), _) => {
+                let start = self.hints.list_starts.get(&self.seen.lists).copied();
+                self.seen.lists += 1;
+                self.list(&list.inner, start);
+            }
+
+            (Inner::Timestamp(timestamp), dialect) => {
+                let timestamp = timestamp.inner.unwrap_or_default();
+                let style = self
+                    .hints
+                    .timestamp_styles
+                    .get(&self.seen.timestamps)
+                    .copied();
+                self.seen.timestamps += 1;
+                match (dialect, style) {
+                    (Dialect::Discord, None) => {
+                        self.out.push_str(&format!("<t:{}>", timestamp.seconds));
+                    }
+                    (Dialect::Discord, Some(style)) => {
+                        let flag = match style {
+                            TimestampStyle::Relative => 'R',
+                            TimestampStyle::ShortDate => 'd',
+                            TimestampStyle::LongDateTime => 'F',
+                            TimestampStyle::Time => 't',
+                        };
+                        self.out
+                            .push_str(&format!("<t:{}:{}>", timestamp.seconds, flag));
+                    }
+                    (_, None) => self.out.push_str(&timestamp.to_string()),
+                    (_, Some(style)) => {
+                        self.out
+                            .push_str(&format_timestamp(&timestamp, style, SystemTime::now()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns a run of backticks longer than any run of backticks in the text,
+/// with at least the given length.
+fn code_fence(text: &str, min_len: usize) -> String {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat(min_len.max(longest_run + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+    use crate::Block;
+
+    fn formatted() -> Block {
+        Block::new()
+            .bold("b")
+            .italic("i")
+            .underline("u")
+            .strikethrough("s")
+            .spoiler("p")
+    }
+
+    #[test]
+    fn renders_formatting_per_dialect() {
+        assert_eq!(formatted().render(Dialect::Plain), "biusp");
+        assert_eq!(formatted().render(Dialect::Markdown), "**b***i*u~~s~~p");
+        assert_eq!(
+            formatted().render(Dialect::Discord),
+            "**b***i*__u__~~s~~||p||"
+        );
+        assert_eq!(
+            formatted().render(Dialect::Irc),
+            "\x02b\x02\x1di\x1d\x1fu\x1f\x1es\x1e\x0301,01p\x03"
+        );
+    }
+
+    #[test]
+    fn code_fences_are_longer_than_the_code() {
+        let block = Block::new().inline_code("a `b` c");
+        assert_eq!(block.render(Dialect::Markdown), "``a `b` c``");
+
+        let block = Block::new().inline_code("`tick");
+        assert_eq!(block.render(Dialect::Markdown), "`` `tick ``");
+
+        let block = Block::new()
+            .text("Example:")
+            .fenced_code("md", "```\ncode\n```\n");
+        assert_eq!(
+            block.render(Dialect::Discord),
+            "Example:\n````md\n```\ncode\n```\n````"
+        );
+        assert_eq!(block.render(Dialect::Plain), "Example:\n```\ncode\n```");
+    }
+
+    #[test]
+    fn renders_links() {
+        let block = Block::new()
+            .link("https://example.com", "docs")
+            .text(" ")
+            .link("https://example.org", "https://example.org");
+
+        assert_eq!(
+            block.render(Dialect::Markdown),
+            "[docs](https://example.com) [https://example.org](https://example.org)"
+        );
+        assert_eq!(
+            block.render(Dialect::Plain),
+            "docs (https://example.com) https://example.org"
+        );
+    }
+
+    #[test]
+    fn renders_block_level_elements_on_their_own_lines() {
+        let block = Block::new()
+            .heading(5, "Title")
+            .text("Intro")
+            .blockquote(Block::new().text("one").fenced_code("", "two"))
+            .list(vec![
+                Block::new().text("first"),
+                Block::new().text("second").list(vec!["nested"]),
+            ]);
+
+        assert_eq!(
+            block.render(Dialect::Markdown),
+            "##### Title\nIntro\n> one\n> ```\n> two\n> ```\n- first\n- second\n  - nested"
+        );
+        assert_eq!(
+            block.render(Dialect::Discord),
+            "### Title\nIntro\n> one\n> ```\n> two\n> ```\n- first\n- second\n  - nested"
+        );
+        assert_eq!(
+            block.render(Dialect::Irc),
+            "\x02Title\x02\nIntro\n> one\n> two\n- first\n- second\n  - nested"
+        );
+    }
+
+    #[test]
+    fn renders_mentions() {
+        let block = Block::new().text("thanks ").mention("1234", "belak");
+
+        assert_eq!(block.render(Dialect::Plain), "thanks @belak");
+        assert_eq!(block.render(Dialect::Markdown), "thanks @belak");
+        assert_eq!(block.render(Dialect::Discord), "thanks <@1234>");
+        assert_eq!(block.render(Dialect::Irc), "thanks belak");
+    }
+
+    #[test]
+    fn mentions_fall_back_to_at_display_name() {
+        let block: proto::Block = Block::new().mention("1234", "belak").into();
+        let Some(Inner::Link(link)) = &block.inner else {
+            panic!("expected a link block");
+        };
+
+        assert_eq!(link.url, "seabird://user/1234");
+        assert_eq!(block.plain, "@belak");
+        assert_eq!(
+            render(link.inner.as_deref().unwrap(), Dialect::Plain),
+            "@belak"
+        );
+    }
+
+    #[test]
+    fn renders_timestamps() {
+        let block = Block::new().timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+        assert_eq!(block.render(Dialect::Discord), "<t:1700000000>");
+        assert_eq!(block.render(Dialect::Plain), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn renders_styled_timestamps() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let styled = |style| Block::new().styled_timestamp(time, style);
+
+        assert_eq!(
+            styled(TimestampStyle::ShortDate).render(Dialect::Markdown),
+            "2023-11-14"
+        );
+        assert_eq!(
+            styled(TimestampStyle::LongDateTime).render(Dialect::Irc),
+            "2023-11-14 22:13:20 UTC"
+        );
+        assert_eq!(
+            styled(TimestampStyle::Time).render(Dialect::Plain),
+            "22:13 UTC"
+        );
+        assert!(styled(TimestampStyle::Relative)
+            .render(Dialect::Plain)
+            .ends_with(" ago"));
+
+        assert_eq!(
+            styled(TimestampStyle::Relative).render(Dialect::Discord),
+            "<t:1700000000:R>"
+        );
+        assert_eq!(
+            styled(TimestampStyle::ShortDate).render(Dialect::Discord),
+            "<t:1700000000:d>"
+        );
+        assert_eq!(
+            styled(TimestampStyle::LongDateTime).render(Dialect::Discord),
+            "<t:1700000000:F>"
+        );
+        assert_eq!(
+            styled(TimestampStyle::Time).render(Dialect::Discord),
+            "<t:1700000000:t>"
+        );
+    }
+
+    #[test]
+    fn formats_relative_offsets() {
+        let timestamp = prost_types::Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 0,
+        };
+        let relative_to = |offset: i64| {
+            let now = UNIX_EPOCH + Duration::from_secs((1_700_000_000 - offset) as u64);
+            format_timestamp(&timestamp, TimestampStyle::Relative, now)
+        };
+
+        assert_eq!(relative_to(0), "now");
+        assert_eq!(relative_to(1), "in 1 second");
+        assert_eq!(relative_to(300), "in 5 minutes");
+        assert_eq!(relative_to(-7200), "2 hours ago");
+        assert_eq!(relative_to(-86400 * 45), "1 month ago");
+        assert_eq!(relative_to(86400 * 800), "in 2 years");
+    }
+
+    #[test]
+    fn formats_dates_across_calendar_edges() {
+        let date = |seconds| {
+            format_timestamp(
+                &prost_types::Timestamp { seconds, nanos: 0 },
+                TimestampStyle::ShortDate,
+                UNIX_EPOCH,
+            )
+        };
+
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(-1), "1969-12-31");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(951_868_800), "2000-03-01");
+        assert_eq!(date(4_107_542_400), "2100-03-01");
+    }
+}
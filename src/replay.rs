@@ -0,0 +1,353 @@
+//! Recording and replaying event streams.
+//!
+//! An [`EventRecorder`] writes events to a file as they're received, along
+//! with the time each one arrived. The recording can later be opened with
+//! [`EventStream::replay`](crate::EventStream::replay), which yields the same
+//! events through the same interface as a live stream, so a bot can be run
+//! against it without a seabird instance.
+//!
+//! Recordings are a sequence of length-delimited protobuf messages, each
+//! holding an event and the time it was received. This keeps every field of
+//! the event, including any blocks, exactly as the server sent it.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::collections::HashMap;
+//!
+//! use seabird::replay::{EventRecorder, ReplaySpeed};
+//! use seabird::{ClientConfig, EventStream, SeabirdClient};
+//!
+//! # async fn example() -> seabird::error::Result<()> {
+//! # let config = ClientConfig { url: "".into(), token: "".into() };
+//! // Record events from a live stream...
+//! let mut client = SeabirdClient::new(config).await?;
+//! let mut events = client
+//!     .stream_events(HashMap::new())
+//!     .await?
+//!     .record_to(EventRecorder::create("events.bin").await?);
+//! while let Some(event) = events.next().await? {
+//!     // Handle the event as usual.
+//! }
+//!
+//! // ...and replay them later, twice as fast as they arrived.
+//! let mut events = EventStream::replay("events.bin", ReplaySpeed::Scaled(2.0)).await?;
+//! while let Some(event) = events.next().await? {
+//!     // Handle the event as usual.
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::path::Path;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use prost::Message;
+use tokio::fs::File;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+
+use crate::error::Result;
+use crate::proto;
+
+/// A single event in a recording.
+#[derive(Clone, PartialEq, prost::Message)]
+struct RecordedEvent {
+    #[prost(message, optional, tag = "1")]
+    received_at: Option<prost_types::Timestamp>,
+    #[prost(message, optional, tag = "2")]
+    event: Option<proto::Event>,
+}
+
+/// How quickly to replay a recording.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplaySpeed {
+    /// Waits between events for as long as they were apart when recorded.
+    RealTime,
+    /// Waits between events for the recorded gap divided by the given
+    /// factor, so `Scaled(2.0)` replays twice as fast. Factors which aren't
+    /// positive replay without waiting.
+    Scaled(f64),
+    /// Yields every event without waiting.
+    Instant,
+}
+
+impl ReplaySpeed {
+    fn delay(self, gap: Duration) -> Duration {
+        match self {
+            ReplaySpeed::RealTime => gap,
+            ReplaySpeed::Scaled(factor) if factor > 0.0 && factor.is_finite() => {
+                gap.div_f64(factor)
+            }
+            ReplaySpeed::Scaled(_) | ReplaySpeed::Instant => Duration::ZERO,
+        }
+    }
+}
+
+/// Writes events to a recording.
+///
+/// Recorders are usually attached to a live stream with
+/// [`EventStream::record_to`](crate::EventStream::record_to), but events can
+/// also be recorded by hand. Each event is flushed as soon as it's recorded,
+/// so a recording is complete up to the last event even if the process is
+/// stopped.
+pub struct EventRecorder {
+    writer: Pin<Box<dyn AsyncWrite + Send>>,
+}
+
+impl fmt::Debug for EventRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventRecorder").finish_non_exhaustive()
+    }
+}
+
+impl EventRecorder {
+    /// Creates a recorder which writes to the given writer.
+    pub fn new(writer: impl AsyncWrite + Send + 'static) -> Self {
+        Self {
+            writer: Box::pin(writer),
+        }
+    }
+
+    /// Creates a recorder which writes to a new file at the given path,
+    /// replacing any existing file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be created.
+    pub async fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+        Ok(Self::new(file))
+    }
+
+    /// Records an event as received now.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the event can't be written.
+    pub async fn record(&mut self, event: &proto::Event) -> Result<()> {
+        self.record_at(event, SystemTime::now()).await
+    }
+
+    /// Records an event as received at the given time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the event can't be written.
+    pub async fn record_at(&mut self, event: &proto::Event, received_at: SystemTime) -> Result<()> {
+        let record = RecordedEvent {
+            received_at: Some(received_at.into()),
+            event: Some(event.clone()),
+        };
+        self.writer
+            .write_all(&record.encode_length_delimited_to_vec())
+            .await
+            .context("Failed to write recorded event")?;
+        self.writer
+            .flush()
+            .await
+            .context("Failed to write recorded event")?;
+        Ok(())
+    }
+}
+
+/// Reads events back from a recording, waiting between them according to the
+/// replay speed.
+pub(crate) struct Replay {
+    reader: Pin<Box<dyn AsyncBufRead + Send>>,
+    speed: ReplaySpeed,
+    last_received_at: Option<SystemTime>,
+}
+
+impl fmt::Debug for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("speed", &self.speed)
+            .field("last_received_at", &self.last_received_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Replay {
+    pub(crate) fn new(reader: impl AsyncBufRead + Send + 'static, speed: ReplaySpeed) -> Self {
+        Self {
+            reader: Box::pin(reader),
+            speed,
+            last_received_at: None,
+        }
+    }
+
+    pub(crate) async fn open(path: &Path, speed: ReplaySpeed) -> Result<Self> {
+        let file = File::open(path)
+            .await
+            .with_context(|| format!("Failed to open recording {}", path.display()))?;
+        Ok(Self::new(BufReader::new(file), speed))
+    }
+
+    pub(crate) async fn next(&mut self) -> Result<Option<proto::Event>> {
+        let Some(record) = self.read_record().await? else {
+            return Ok(None);
+        };
+
+        let received_at = record
+            .received_at
+            .and_then(|time| SystemTime::try_from(time).ok());
+        if let (Some(last), Some(received_at)) = (self.last_received_at, received_at) {
+            let gap = received_at.duration_since(last).unwrap_or_default();
+            let delay = self.speed.delay(gap);
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+        if received_at.is_some() {
+            self.last_received_at = received_at;
+        }
+
+        Ok(Some(record.event.unwrap_or_default()))
+    }
+
+    /// Reads the next record, or `None` at the end of the recording.
+    async fn read_record(&mut self) -> Result<Option<RecordedEvent>> {
+        if self
+            .reader
+            .fill_buf()
+            .await
+            .context("Failed to read recording")?
+            .is_empty()
+        {
+            return Ok(None);
+        }
+
+        // The length prefix is a varint of at most 10 bytes, each of which
+        // has its high bit set if there's another byte after it.
+        let mut len: u64 = 0;
+        for i in 0..10 {
+            let mut byte = [0];
+            self.reader
+                .read_exact(&mut byte)
+                .await
+                .context("Recording ends in the middle of an event")?;
+            len |= u64::from(byte[0] & 0x7f) << (7 * i);
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+
+        // Reading through take avoids allocating the whole length up front,
+        // in case the recording is corrupt.
+        let mut buf = Vec::new();
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut buf)
+            .await
+            .context("Failed to read recording")?;
+        anyhow::ensure!(
+            buf.len() as u64 == len,
+            "Recording ends in the middle of an event"
+        );
+        let record = RecordedEvent::decode(buf.as_slice()).context("Invalid recorded event")?;
+        Ok(Some(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::EventStream;
+
+    fn command(name: &str) -> proto::Event {
+        proto::Event {
+            tags: HashMap::from([("id".to_string(), name.to_string())]),
+            inner: Some(proto::event::Inner::Command(proto::CommandEvent {
+                source: None,
+                command: name.to_string(),
+                arg: "some args".to_string(),
+            })),
+        }
+    }
+
+    #[test]
+    fn speeds_scale_the_recorded_gaps() {
+        let gap = Duration::from_secs(4);
+        assert_eq!(ReplaySpeed::RealTime.delay(gap), gap);
+        assert_eq!(ReplaySpeed::Scaled(2.0).delay(gap), Duration::from_secs(2));
+        assert_eq!(ReplaySpeed::Scaled(0.5).delay(gap), Duration::from_secs(8));
+        assert_eq!(ReplaySpeed::Scaled(0.0).delay(gap), Duration::ZERO);
+        assert_eq!(ReplaySpeed::Scaled(f64::NAN).delay(gap), Duration::ZERO);
+        assert_eq!(ReplaySpeed::Instant.delay(gap), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_events_in_order() {
+        let (writer, reader) = tokio::io::duplex(1 << 16);
+        let mut recorder = EventRecorder::new(writer);
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        recorder.record_at(&command("first"), start).await.unwrap();
+        recorder
+            .record_at(&command("second"), start + Duration::from_secs(3600))
+            .await
+            .unwrap();
+        drop(recorder);
+
+        let mut events = EventStream::replay_from(BufReader::new(reader), ReplaySpeed::Instant);
+        assert_eq!(events.next().await.unwrap(), Some(command("first")));
+        assert_eq!(events.next().await.unwrap(), Some(command("second")));
+        assert_eq!(events.next().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn replayed_events_can_be_recorded_again() {
+        let path = std::env::temp_dir().join(format!("seabird-replay-{}.bin", std::process::id()));
+        let mut recorder = EventRecorder::create(&path).await.unwrap();
+        recorder.record(&command("first")).await.unwrap();
+        recorder.record(&command("second")).await.unwrap();
+
+        let (writer, reader) = tokio::io::duplex(1 << 16);
+        let mut events = EventStream::replay(&path, ReplaySpeed::Instant)
+            .await
+            .unwrap()
+            .record_to(EventRecorder::new(writer));
+        while events.next().await.unwrap().is_some() {}
+        drop(events);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut events = EventStream::replay_from(BufReader::new(reader), ReplaySpeed::Instant);
+        assert_eq!(events.next().await.unwrap(), Some(command("first")));
+        assert_eq!(events.next().await.unwrap(), Some(command("second")));
+        assert_eq!(events.next().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn truncated_recordings_are_errors() {
+        let record = RecordedEvent {
+            received_at: None,
+            event: Some(command("first")),
+        }
+        .encode_length_delimited_to_vec();
+
+        let truncated = record[..record.len() - 1].to_vec();
+        let mut events =
+            EventStream::replay_from(std::io::Cursor::new(truncated), ReplaySpeed::Instant);
+        assert!(events.next().await.is_err());
+
+        let mut events = EventStream::replay_from(&[0x80][..], ReplaySpeed::Instant);
+        assert!(events.next().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn opening_a_missing_recording_fails() {
+        let path = std::env::temp_dir().join("seabird-replay-does-not-exist.bin");
+        assert!(EventStream::replay(path, ReplaySpeed::Instant)
+            .await
+            .is_err());
+    }
+}
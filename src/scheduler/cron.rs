@@ -0,0 +1,450 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+
+use crate::civil::{civil_from_days, days_from_civil};
+use crate::error::{Error, Result};
+
+/// How many years ahead to look for the next time a schedule matches. Every
+/// valid day of the year, including 29 February, comes around within this.
+const SEARCH_YEARS: i64 = 8;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A cron schedule, evaluated in UTC.
+///
+/// Schedules use the standard five fields: minute, hour, day of the month,
+/// month and day of the week. Each field can be `*`, a value, a range such as
+/// `1-5`, a step such as `*/15` or `10-50/20`, or a comma-separated list of
+/// these. Months and days of the week can also be given by their three
+/// letter English names, and Sunday is both `0` and `7`.
+///
+/// The `@yearly` (or `@annually`), `@monthly`, `@weekly`, `@daily` (or
+/// `@midnight`) and `@hourly` shorthands are also supported.
+///
+/// As in most cron implementations, if both the day of the month and the day
+/// of the week are restricted, a day matches if either of them does.
+///
+/// With the `serde` feature, schedules are serialized as their expression.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// use seabird::scheduler::CronSchedule;
+///
+/// // 09:30 every weekday.
+/// let schedule: CronSchedule = "30 9 * * mon-fri".parse().unwrap();
+///
+/// // The Unix epoch was a Thursday, so the next run is the same morning.
+/// assert_eq!(
+///     schedule.next_after(UNIX_EPOCH),
+///     Some(UNIX_EPOCH + Duration::from_secs(9 * 3600 + 30 * 60))
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl fmt::Debug for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CronSchedule")
+            .field(&self.expression)
+            .finish()
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        Self::parse(expression)
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = Error;
+
+    fn try_from(expression: String) -> Result<Self> {
+        Self::parse(&expression)
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+impl CronSchedule {
+    /// Parses a cron expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is invalid, or if it can never
+    /// match, such as `0 0 30 feb *`.
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        let expanded = match expression {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            bail!(
+                "Invalid cron expression {:?}: expected 5 fields, found {}",
+                expression,
+                fields.len()
+            );
+        };
+
+        let with_context =
+            |field: &str| format!("Invalid {} in cron expression {:?}", field, expression);
+        let mut days_of_week_set = parse_field(days_of_week, 0, 7, &DAY_NAMES)
+            .with_context(|| with_context("day of the week"))?;
+        // Sunday can be written as either 0 or 7.
+        if days_of_week_set & (1 << 7) != 0 {
+            days_of_week_set |= 1;
+        }
+
+        let schedule = Self {
+            expression: expression.to_string(),
+            minutes: parse_field(minutes, 0, 59, &[]).with_context(|| with_context("minute"))?,
+            hours: parse_field(hours, 0, 23, &[]).with_context(|| with_context("hour"))?,
+            days_of_month: parse_field(days_of_month, 1, 31, &[])
+                .with_context(|| with_context("day of the month"))?,
+            months: parse_field(months, 1, 12, &MONTH_NAMES)
+                .with_context(|| with_context("month"))?,
+            days_of_week: days_of_week_set,
+            // Fields starting with *, such as */2, still count as
+            // unrestricted here, as in Vixie cron.
+            day_of_month_restricted: !days_of_month.starts_with('*'),
+            day_of_week_restricted: !days_of_week.starts_with('*'),
+        };
+
+        anyhow::ensure!(
+            schedule.next_after(UNIX_EPOCH).is_some(),
+            "Cron expression {:?} never matches",
+            expression
+        );
+        Ok(schedule)
+    }
+
+    /// Returns the next time the schedule matches, strictly after the given
+    /// time, or `None` if there isn't one in the next few years.
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        // Minutes since the epoch, starting from the minute after the given
+        // time.
+        let mut minute = i64::try_from(secs / 60).ok()? + 1;
+        let (start_year, _, _) = civil_from_days(minute.div_euclid(1440));
+
+        loop {
+            let days = minute.div_euclid(1440);
+            let (year, month, day) = civil_from_days(days);
+            if year > start_year + SEARCH_YEARS {
+                return None;
+            }
+
+            if !has(self.months, month) {
+                let (year, month) = if month == 12 {
+                    (year + 1, 1)
+                } else {
+                    (year, month + 1)
+                };
+                minute = days_from_civil(year, month, 1) * 1440;
+                continue;
+            }
+
+            let weekday = (days + 4).rem_euclid(7) as u32;
+            if !self.day_matches(day, weekday) {
+                minute = (days + 1) * 1440;
+                continue;
+            }
+
+            let minute_of_day = minute.rem_euclid(1440);
+            if !has(self.hours, (minute_of_day / 60) as u32) {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+
+            if !has(self.minutes, (minute_of_day % 60) as u32) {
+                minute += 1;
+                continue;
+            }
+
+            return Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(minute).ok()? * 60));
+        }
+    }
+
+    fn day_matches(&self, day: u32, weekday: u32) -> bool {
+        let day_of_month = has(self.days_of_month, day);
+        let day_of_week = has(self.days_of_week, weekday);
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses one field into a bit set of the values it matches.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .with_context(|| format!("invalid step {:?}", step))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names)?,
+                parse_value(end, min, max, names)?,
+            )
+        } else {
+            let start = parse_value(range, min, max, names)?;
+            // A single value with a step, such as 5/15, runs to the end.
+            (start, if part.contains('/') { max } else { start })
+        };
+        anyhow::ensure!(start <= end, "range {:?} is backwards", range);
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32> {
+    let lower = value.to_ascii_lowercase();
+    let parsed = match names.iter().position(|name| *name == lower) {
+        Some(index) => index as u32 + min,
+        None => value
+            .parse()
+            .ok()
+            .with_context(|| format!("invalid value {:?}", value))?,
+    };
+    anyhow::ensure!(
+        (min..=max).contains(&parsed),
+        "{} is out of range {}-{}",
+        parsed,
+        min,
+        max
+    );
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a UTC time as a `SystemTime`.
+    fn at(year: i64, month: u32, day: u32, hour: u64, minute: u64) -> SystemTime {
+        let days = u64::try_from(days_from_civil(year, month, day)).unwrap();
+        UNIX_EPOCH + Duration::from_secs(days * 86_400 + hour * 3600 + minute * 60)
+    }
+
+    /// Returns the next run of a schedule after a time.
+    fn next(expression: &str, after: SystemTime) -> Option<SystemTime> {
+        CronSchedule::parse(expression).unwrap().next_after(after)
+    }
+
+    // 1 January 2024 was a Monday.
+    const YEAR: i64 = 2024;
+
+    #[test]
+    fn runs_strictly_after_the_given_time() {
+        let start = at(YEAR, 1, 1, 9, 30);
+        assert_eq!(next("30 9 * * *", start), Some(at(YEAR, 1, 2, 9, 30)));
+        assert_eq!(
+            next("30 9 * * *", start - Duration::from_secs(1)),
+            Some(start)
+        );
+    }
+
+    #[test]
+    fn names_are_case_insensitive() {
+        let start = at(YEAR, 1, 1, 0, 0);
+        assert_eq!(
+            next("0 12 * jan,JUL Sat", start),
+            next("0 12 * 1,7 6", start)
+        );
+        assert_eq!(next("0 12 * Feb-mar *", start), Some(at(YEAR, 2, 1, 12, 0)));
+        assert_eq!(next("0 12 * * tue-thu", start), Some(at(YEAR, 1, 2, 12, 0)));
+        assert!(CronSchedule::parse("0 12 * * funday").is_err());
+    }
+
+    #[test]
+    fn sunday_is_0_or_7() {
+        let start = at(YEAR, 1, 1, 0, 0);
+        assert_eq!(next("0 0 * * 0", start), Some(at(YEAR, 1, 7, 0, 0)));
+        assert_eq!(next("0 0 * * 7", start), Some(at(YEAR, 1, 7, 0, 0)));
+        assert_eq!(next("0 0 * * sun", start), Some(at(YEAR, 1, 7, 0, 0)));
+        assert_eq!(next("0 0 * * 6-7", start), Some(at(YEAR, 1, 6, 0, 0)));
+        assert_eq!(
+            next("0 0 * * 6-7", at(YEAR, 1, 6, 0, 0)),
+            Some(at(YEAR, 1, 7, 0, 0))
+        );
+    }
+
+    #[test]
+    fn steps() {
+        assert_eq!(
+            next("*/15 * * * *", at(YEAR, 1, 1, 10, 7)),
+            Some(at(YEAR, 1, 1, 10, 15))
+        );
+        assert_eq!(
+            next("10-50/20 * * * *", at(YEAR, 1, 1, 10, 31)),
+            Some(at(YEAR, 1, 1, 10, 50))
+        );
+        assert_eq!(
+            next("10-50/20 * * * *", at(YEAR, 1, 1, 10, 50)),
+            Some(at(YEAR, 1, 1, 11, 10))
+        );
+        // A single value with a step runs to the end of the range.
+        assert_eq!(
+            next("5/15 * * * *", at(YEAR, 1, 1, 10, 50)),
+            Some(at(YEAR, 1, 1, 11, 5))
+        );
+        assert_eq!(
+            next("0 */6 * * *", at(YEAR, 1, 1, 13, 0)),
+            Some(at(YEAR, 1, 1, 18, 0))
+        );
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+    }
+
+    #[test]
+    fn restricted_days_of_the_month_and_week_match_either() {
+        // The first Friday comes before the 13th.
+        let start = at(YEAR, 1, 1, 0, 0);
+        assert_eq!(next("0 0 13 * fri", start), Some(at(YEAR, 1, 5, 0, 0)));
+        // Saturday the 13th isn't a Friday, but still matches.
+        assert_eq!(
+            next("0 0 13 * fri", at(YEAR, 1, 12, 0, 0)),
+            Some(at(YEAR, 1, 13, 0, 0))
+        );
+        // A day field starting with * doesn't count as restricted, so both
+        // have to match: the first Monday on an odd day after the 1st.
+        assert_eq!(next("0 0 */2 * mon", start), Some(at(YEAR, 1, 15, 0, 0)));
+        // Only the day of the month is restricted.
+        assert_eq!(next("0 0 13 * *", start), Some(at(YEAR, 1, 13, 0, 0)));
+    }
+
+    #[test]
+    fn shorthands() {
+        let start = at(YEAR, 3, 1, 10, 7);
+        for (expression, expected) in [
+            ("@yearly", at(YEAR + 1, 1, 1, 0, 0)),
+            ("@annually", at(YEAR + 1, 1, 1, 0, 0)),
+            ("@monthly", at(YEAR, 4, 1, 0, 0)),
+            // 1 March 2024 was a Friday.
+            ("@weekly", at(YEAR, 3, 3, 0, 0)),
+            ("@daily", at(YEAR, 3, 2, 0, 0)),
+            ("@midnight", at(YEAR, 3, 2, 0, 0)),
+            ("@hourly", at(YEAR, 3, 1, 11, 0)),
+        ] {
+            assert_eq!(next(expression, start), Some(expected), "{}", expression);
+        }
+        assert_eq!(
+            CronSchedule::parse(" @daily ").unwrap().to_string(),
+            "@daily"
+        );
+        assert!(CronSchedule::parse("@fortnightly").is_err());
+    }
+
+    #[test]
+    fn leap_days() {
+        assert_eq!(
+            next("0 0 29 feb *", at(YEAR, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+        // 2100 isn't a leap year, so this is the longest possible wait.
+        assert_eq!(
+            next("0 0 29 feb *", at(2097, 3, 1, 0, 0)),
+            Some(at(2104, 2, 29, 0, 0))
+        );
+        assert_eq!(
+            next("0 0 28-29 feb *", at(2100, 2, 28, 0, 0)),
+            Some(at(2101, 2, 28, 0, 0))
+        );
+    }
+
+    #[test]
+    fn schedules_which_never_match_are_rejected() {
+        assert!(CronSchedule::parse("0 0 30 feb *").is_err());
+        assert!(CronSchedule::parse("0 0 31 apr,jun,sep,nov *").is_err());
+        // Months with 31 days make this valid.
+        assert_eq!(
+            next("0 0 31 * *", at(YEAR, 2, 1, 0, 0)),
+            Some(at(YEAR, 3, 31, 0, 0))
+        );
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for expression in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "5-1 * * * *",
+            "a * * * *",
+            "1,,2 * * * *",
+        ] {
+            assert!(
+                CronSchedule::parse(expression).is_err(),
+                "{:?} should be invalid",
+                expression
+            );
+        }
+    }
+}
@@ -0,0 +1,594 @@
+//! Sending messages at a later time or on a schedule.
+//!
+//! A [`Scheduler`] wraps a [`SeabirdClient`] and sends each job's message to
+//! its channel when the job is due. One-shot jobs run until their message is
+//! sent and are then removed, while cron jobs run every time their
+//! [`CronSchedule`] matches until they're cancelled.
+//!
+//! Pending jobs only live in memory unless the scheduler has a [`JobStore`],
+//! which is told about every job as it's added, run or removed, and which the
+//! scheduler loads jobs from when it's created. With the `serde` feature,
+//! [`Job`] implements `Serialize` and `Deserialize` to make stores easy to
+//! write.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use seabird::scheduler::{Schedule, Scheduler};
+//! use seabird::{ClientConfig, SeabirdClient};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = SeabirdClient::new(ClientConfig {
+//!         url: "https://seabird.example.com".to_string(),
+//!         token: "your-token-here".to_string(),
+//!     })
+//!     .await?;
+//!
+//!     let scheduler = Scheduler::new(client);
+//!     scheduler.schedule(
+//!         "channel-id",
+//!         "Stand-up in 5 minutes!",
+//!         Schedule::cron("55 9 * * mon-fri")?,
+//!     )?;
+//!     let reminder = scheduler.schedule(
+//!         "channel-id",
+//!         "Time to stretch",
+//!         Schedule::after(Duration::from_secs(3600)),
+//!     )?;
+//!     scheduler.cancel(reminder)?;
+//!
+//!     scheduler.run().await;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::Notify;
+
+use crate::client::{MessageContent, SeabirdClient};
+use crate::error::Result;
+use crate::telemetry;
+
+mod cron;
+
+pub use cron::CronSchedule;
+
+/// How long to wait before retrying a one-shot job whose message failed to
+/// send.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// The ID of a scheduled job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct JobId(pub u64);
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// When a job runs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Schedule {
+    /// Runs once at the given time, or as soon as possible if it has passed.
+    Once(SystemTime),
+    /// Runs every time the cron schedule matches.
+    Cron(CronSchedule),
+}
+
+impl Schedule {
+    /// Runs once at the given time.
+    pub fn at(time: SystemTime) -> Self {
+        Schedule::Once(time)
+    }
+
+    /// Runs once after the given delay.
+    pub fn after(delay: Duration) -> Self {
+        Schedule::Once(SystemTime::now() + delay)
+    }
+
+    /// Runs on a cron schedule. See [`CronSchedule`] for the syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is invalid.
+    pub fn cron(expression: &str) -> Result<Self> {
+        CronSchedule::parse(expression).map(Schedule::Cron)
+    }
+
+    /// Returns the first time this schedule runs after the given time, or
+    /// `None` if it never runs again.
+    fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        match self {
+            Schedule::Once(at) => Some(*at),
+            Schedule::Cron(cron) => cron.next_after(time),
+        }
+    }
+}
+
+/// A message to send to a channel on a schedule.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Job {
+    /// The ID of the job, unique within a scheduler.
+    pub id: JobId,
+    /// The channel to send the message to.
+    pub channel_id: String,
+    /// The message to send.
+    pub content: MessageContent,
+    /// When the job runs.
+    pub schedule: Schedule,
+    /// The next time the job runs.
+    pub next_run: SystemTime,
+}
+
+/// Persistent storage for pending jobs.
+///
+/// The scheduler calls these methods while holding its internal lock, so
+/// they should be quick. Errors from [`save`](Self::save) and
+/// [`remove`](Self::remove) are returned from the scheduler method which
+/// caused them, or recorded through the telemetry features when a job runs.
+pub trait JobStore: Send + Sync + 'static {
+    /// Loads every stored job.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the jobs can't be read.
+    fn load(&self) -> Result<Vec<Job>>;
+
+    /// Stores a new job, or replaces a stored job with the same ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job can't be written.
+    fn save(&self, job: &Job) -> Result<()>;
+
+    /// Removes a stored job.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job can't be removed.
+    fn remove(&self, id: JobId) -> Result<()>;
+}
+
+#[derive(Default)]
+struct State {
+    jobs: HashMap<JobId, Job>,
+    next_id: u64,
+}
+
+impl State {
+    fn next_run(&self) -> Option<SystemTime> {
+        self.jobs.values().map(|job| job.next_run).min()
+    }
+}
+
+/// Sends scheduled messages through a [`SeabirdClient`].
+///
+/// Jobs can be added and cancelled at any time, including while the
+/// scheduler is running. Clones share the same jobs, so a clone can be
+/// handed to command handlers to let them schedule messages.
+#[derive(Clone)]
+pub struct Scheduler {
+    client: SeabirdClient,
+    state: Arc<Mutex<State>>,
+    changed: Arc<Notify>,
+    store: Option<Arc<dyn JobStore>>,
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("client", &self.client)
+            .field("jobs", &self.jobs())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scheduler {
+    /// Creates a scheduler with no jobs which sends messages with the given
+    /// client.
+    pub fn new(client: SeabirdClient) -> Self {
+        Self {
+            client,
+            state: Arc::new(Mutex::new(State::default())),
+            changed: Arc::new(Notify::new()),
+            store: None,
+        }
+    }
+
+    /// Persists jobs in the given store, first loading any jobs already in
+    /// it.
+    ///
+    /// Loaded one-shot jobs which were due while the scheduler wasn't running
+    /// are run as soon as it starts. Cron jobs skip any runs they missed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the jobs can't be loaded.
+    pub fn with_store(mut self, store: impl JobStore) -> Result<Self> {
+        let jobs = store.load()?;
+        {
+            let now = SystemTime::now();
+            let mut state = self.lock();
+            for mut job in jobs {
+                if let Schedule::Cron(cron) = &job.schedule {
+                    match cron.next_after(now) {
+                        Some(next_run) => job.next_run = next_run,
+                        None => continue,
+                    }
+                }
+                state.next_id = state.next_id.max(job.id.0 + 1);
+                state.jobs.insert(job.id, job);
+            }
+        }
+        self.store = Some(Arc::new(store));
+        self.changed.notify_one();
+        Ok(self)
+    }
+
+    /// Schedules a message to be sent to a channel, returning the ID of the
+    /// new job.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job can't be saved to the store, in which case
+    /// it isn't scheduled.
+    pub fn schedule(
+        &self,
+        channel_id: impl Into<String>,
+        content: impl Into<MessageContent>,
+        schedule: Schedule,
+    ) -> Result<JobId> {
+        let mut state = self.lock();
+        let id = JobId(state.next_id);
+        let Some(next_run) = schedule.next_after(SystemTime::now()) else {
+            anyhow::bail!("Schedule never runs");
+        };
+        let job = Job {
+            id,
+            channel_id: channel_id.into(),
+            content: content.into(),
+            schedule,
+            next_run,
+        };
+
+        if let Some(store) = &self.store {
+            store.save(&job)?;
+        }
+        state.next_id += 1;
+        state.jobs.insert(id, job);
+        drop(state);
+
+        self.changed.notify_one();
+        Ok(id)
+    }
+
+    /// Cancels a job, returning whether it was still pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job can't be removed from the store, in which
+    /// case it's still cancelled.
+    pub fn cancel(&self, id: JobId) -> Result<bool> {
+        let removed = self.lock().jobs.remove(&id).is_some();
+        if removed {
+            self.changed.notify_one();
+            if let Some(store) = &self.store {
+                store.remove(id)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Returns the pending jobs, ordered by when they next run.
+    pub fn jobs(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.lock().jobs.values().cloned().collect();
+        jobs.sort_by_key(|job| (job.next_run, job.id));
+        jobs
+    }
+
+    /// Runs jobs as they become due, forever.
+    ///
+    /// One-shot jobs stay pending until their message is sent. If sending
+    /// fails, the job is retried a minute later until it succeeds or is
+    /// cancelled. Cron jobs are rescheduled for their next run before they're
+    /// sent, so a run whose message fails to send is skipped. Failures are
+    /// recorded through the telemetry features.
+    pub async fn run(&self) {
+        self.run_until(std::future::pending()).await;
+    }
+
+    /// Runs jobs as they become due until `shutdown` completes.
+    ///
+    /// A job which is sending when `shutdown` completes is allowed to finish.
+    pub async fn run_until(&self, shutdown: impl Future<Output = ()> + Send) {
+        let mut shutdown = std::pin::pin!(shutdown);
+        loop {
+            let next_run = self.lock().next_run();
+            let wait = next_run.map(|next_run| {
+                next_run
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default()
+            });
+
+            tokio::select! {
+                biased;
+
+                () = &mut shutdown => return,
+                () = self.changed.notified() => continue,
+                () = sleep(wait) => {}
+            }
+
+            for job in self.take_due() {
+                let result = self
+                    .client
+                    .clone()
+                    .send_message(job.channel_id.clone(), job.content.clone(), None)
+                    .await;
+                if let Err(err) = &result {
+                    telemetry::record_job_error(job.id.0, err);
+                }
+                if let Schedule::Once(_) = job.schedule {
+                    self.finish_once(job.id, result.is_ok());
+                }
+            }
+        }
+    }
+
+    /// Returns every due job, rescheduling cron jobs for their next run.
+    ///
+    /// One-shot jobs are left pending until [`finish_once`](Self::finish_once)
+    /// is called with the result of sending them.
+    fn take_due(&self) -> Vec<Job> {
+        let now = SystemTime::now();
+        let mut state = self.lock();
+        let mut jobs: Vec<Job> = state
+            .jobs
+            .values()
+            .filter(|job| job.next_run <= now)
+            .cloned()
+            .collect();
+
+        for job in &jobs {
+            let Schedule::Cron(cron) = &job.schedule else {
+                continue;
+            };
+            let result = match cron.next_after(now) {
+                Some(next_run) => {
+                    let Some(pending) = state.jobs.get_mut(&job.id) else {
+                        continue;
+                    };
+                    pending.next_run = next_run;
+                    self.store
+                        .as_ref()
+                        .map_or(Ok(()), |store| store.save(pending))
+                }
+                None => {
+                    state.jobs.remove(&job.id);
+                    self.store
+                        .as_ref()
+                        .map_or(Ok(()), |store| store.remove(job.id))
+                }
+            };
+            if let Err(err) = result {
+                telemetry::record_job_error(job.id.0, &err);
+            }
+        }
+
+        jobs.sort_by_key(|job| (job.next_run, job.id));
+        jobs
+    }
+
+    /// Removes a one-shot job once its message is sent, or pushes it back by
+    /// [`RETRY_DELAY`] if sending failed. Jobs cancelled while sending are
+    /// left alone.
+    fn finish_once(&self, id: JobId, sent: bool) {
+        let mut state = self.lock();
+        let result = if sent {
+            match state.jobs.remove(&id) {
+                Some(_) => self.store.as_ref().map_or(Ok(()), |store| store.remove(id)),
+                None => Ok(()),
+            }
+        } else {
+            match state.jobs.get_mut(&id) {
+                Some(job) => {
+                    job.next_run = SystemTime::now() + RETRY_DELAY;
+                    self.store.as_ref().map_or(Ok(()), |store| store.save(job))
+                }
+                None => Ok(()),
+            }
+        };
+        if let Err(err) = result {
+            telemetry::record_job_error(id.0, &err);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Sleeps for the given duration, or forever if there isn't one.
+async fn sleep(duration: Option<Duration>) {
+    match duration {
+        Some(duration) => tokio::time::sleep(duration).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockSeabird, UNAVAILABLE};
+
+    /// A store which keeps jobs in memory.
+    #[derive(Clone, Default)]
+    struct MemoryStore(Arc<Mutex<HashMap<JobId, Job>>>);
+
+    impl MemoryStore {
+        fn ids(&self) -> Vec<JobId> {
+            let mut ids: Vec<JobId> = self.0.lock().unwrap().keys().copied().collect();
+            ids.sort();
+            ids
+        }
+
+        fn get(&self, id: JobId) -> Option<Job> {
+            self.0.lock().unwrap().get(&id).cloned()
+        }
+    }
+
+    impl JobStore for MemoryStore {
+        fn load(&self) -> Result<Vec<Job>> {
+            Ok(self.0.lock().unwrap().values().cloned().collect())
+        }
+
+        fn save(&self, job: &Job) -> Result<()> {
+            self.0.lock().unwrap().insert(job.id, job.clone());
+            Ok(())
+        }
+
+        fn remove(&self, id: JobId) -> Result<()> {
+            self.0.lock().unwrap().remove(&id);
+            Ok(())
+        }
+    }
+
+    fn past() -> Schedule {
+        Schedule::at(SystemTime::now() - Duration::from_secs(1))
+    }
+
+    async fn run_briefly(scheduler: &Scheduler) {
+        scheduler
+            .run_until(tokio::time::sleep(Duration::from_millis(200)))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn one_shot_jobs_are_removed_once_sent() {
+        let (mock, client) = MockSeabird::start().await;
+        let store = MemoryStore::default();
+        let scheduler = Scheduler::new(client).with_store(store.clone()).unwrap();
+
+        let due = scheduler.schedule("chan", "now", past()).unwrap();
+        let later = scheduler
+            .schedule("chan", "later", Schedule::after(Duration::from_secs(3600)))
+            .unwrap();
+        assert_eq!(store.ids(), [due, later]);
+
+        run_briefly(&scheduler).await;
+
+        let messages = mock.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].text, "now");
+        let pending: Vec<JobId> = scheduler.jobs().iter().map(|job| job.id).collect();
+        assert_eq!(pending, [later]);
+        assert_eq!(store.ids(), [later]);
+    }
+
+    #[tokio::test]
+    async fn one_shot_jobs_which_fail_are_retried() {
+        let (mock, client) = MockSeabird::start().await;
+        let store = MemoryStore::default();
+        let scheduler = Scheduler::new(client).with_store(store.clone()).unwrap();
+
+        let id = scheduler.schedule(UNAVAILABLE, "hello", past()).unwrap();
+        run_briefly(&scheduler).await;
+
+        assert!(mock.messages().is_empty());
+        let jobs = scheduler.jobs();
+        assert_eq!(jobs.len(), 1);
+        assert!(jobs[0].next_run > SystemTime::now() + RETRY_DELAY / 2);
+        assert_eq!(store.get(id).unwrap().next_run, jobs[0].next_run);
+    }
+
+    #[tokio::test]
+    async fn cron_jobs_are_rescheduled_before_sending() {
+        let (_mock, client) = MockSeabird::start().await;
+        let store = MemoryStore::default();
+        let scheduler = Scheduler::new(client).with_store(store.clone()).unwrap();
+
+        let id = scheduler
+            .schedule("chan", "tick", Schedule::cron("* * * * *").unwrap())
+            .unwrap();
+        let past = SystemTime::now() - Duration::from_secs(120);
+        scheduler.lock().jobs.get_mut(&id).unwrap().next_run = past;
+
+        let due = scheduler.take_due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].next_run, past);
+        let next_run = scheduler.jobs()[0].next_run;
+        assert!(next_run > SystemTime::now());
+        assert_eq!(store.get(id).unwrap().next_run, next_run);
+        assert!(scheduler.take_due().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancelled_jobs_are_removed_from_the_store() {
+        let (mock, client) = MockSeabird::start().await;
+        let store = MemoryStore::default();
+        let scheduler = Scheduler::new(client).with_store(store.clone()).unwrap();
+
+        let id = scheduler.schedule("chan", "never", past()).unwrap();
+        assert!(scheduler.cancel(id).unwrap());
+        assert!(!scheduler.cancel(id).unwrap());
+        assert!(store.ids().is_empty());
+
+        run_briefly(&scheduler).await;
+        assert!(mock.messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stores_are_loaded_on_creation() {
+        let (_mock, client) = MockSeabird::start().await;
+        let store = MemoryStore::default();
+        let stale = SystemTime::now() - Duration::from_secs(86_400);
+        for (id, schedule) in [
+            (3, Schedule::at(stale)),
+            (7, Schedule::cron("0 0 * * *").unwrap()),
+        ] {
+            store
+                .save(&Job {
+                    id: JobId(id),
+                    channel_id: "chan".to_string(),
+                    content: "loaded".into(),
+                    schedule,
+                    next_run: stale,
+                })
+                .unwrap();
+        }
+
+        let scheduler = Scheduler::new(client).with_store(store.clone()).unwrap();
+        let jobs = scheduler.jobs();
+        assert_eq!(jobs[0].id, JobId(3));
+        assert_eq!(jobs[0].next_run, stale);
+        // Cron jobs skip the runs they missed.
+        assert_eq!(jobs[1].id, JobId(7));
+        assert!(jobs[1].next_run > SystemTime::now());
+
+        // New IDs don't clash with loaded ones.
+        let id = scheduler
+            .schedule("chan", "new", Schedule::after(Duration::from_secs(60)))
+            .unwrap();
+        assert_eq!(id, JobId(8));
+    }
+}
@@ -0,0 +1,415 @@
+//! Serde support for [`Block`] and [`MessageContent`](crate::MessageContent).
+//!
+//! Blocks are serialized as a list of nodes, where each node is an object with
+//! a single key naming the node type:
+//!
+//! ```json
+//! [
+//!   { "text": "Hello " },
+//!   { "bold": [{ "text": "world" }] },
+//!   { "link": { "url": "https://example.com", "content": [{ "text": "a link" }] } }
+//! ]
+//! ```
+//!
+//! The `plain` fallback text on [`proto::Block`] is not serialized.
+
+use std::time::SystemTime;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::block::{Block, Counts, Hints};
+use crate::proto;
+use crate::render::TimestampStyle;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Node {
+    Text(String),
+    Container(Vec<Node>),
+    Bold(Vec<Node>),
+    Italic(Vec<Node>),
+    Underline(Vec<Node>),
+    Strikethrough(Vec<Node>),
+    Spoiler(Vec<Node>),
+    Blockquote(Vec<Node>),
+    InlineCode(String),
+    FencedCode {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        info: String,
+        text: String,
+    },
+    Link {
+        url: String,
+        content: Vec<Node>,
+    },
+    Heading {
+        level: i32,
+        content: Vec<Node>,
+    },
+    List(Vec<Vec<Node>>),
+    OrderedList {
+        start: u64,
+        items: Vec<Vec<Node>>,
+    },
+    Timestamp(TimestampNode),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TimestampNode {
+    /// An RFC 3339 timestamp, such as `2024-01-01T00:00:00Z`.
+    Unstyled(String),
+    Styled {
+        time: String,
+        style: TimestampStyle,
+    },
+}
+
+/// Converts block trees into nodes, keeping track of the position of each
+/// block among the blocks with hints.
+struct ToNodes<'h> {
+    hints: &'h Hints,
+    seen: Counts,
+}
+
+impl<'h> ToNodes<'h> {
+    fn new(hints: &'h Hints) -> Self {
+        Self {
+            hints,
+            seen: Counts::default(),
+        }
+    }
+
+    /// Converts a list of blocks into nodes, skipping any blocks without
+    /// content.
+    fn nodes<'a>(&mut self, blocks: impl IntoIterator<Item = &'a proto::Block>) -> Vec<Node> {
+        blocks
+            .into_iter()
+            .filter_map(|block| self.node(block))
+            .collect()
+    }
+
+    /// Converts the inner block of a formatting block into nodes. Containers
+    /// are flattened into their children to keep the output readable.
+    fn inner_nodes(&mut self, inner: Option<&proto::Block>) -> Vec<Node> {
+        match inner {
+            Some(proto::Block {
+                inner: Some(proto::block::Inner::Container(container)),
+                ..
+            }) => self.nodes(&container.inner),
+            Some(block) => self.nodes(Some(block)),
+            None => Vec::new(),
+        }
+    }
+
+    fn node(&mut self, block: &proto::Block) -> Option<Node> {
+        use proto::block::Inner;
+
+        let node = match block.inner.as_ref()? {
+            Inner::Text(text) => Node::Text(text.text.clone()),
+            Inner::Container(container) => Node::Container(self.nodes(&container.inner)),
+            Inner::Bold(bold) => Node::Bold(self.inner_nodes(bold.inner.as_deref())),
+            Inner::Italics(italics) => Node::Italic(self.inner_nodes(italics.inner.as_deref())),
+            Inner::Underline(underline) => {
+                Node::Underline(self.inner_nodes(underline.inner.as_deref()))
+            }
+            Inner::Strikethrough(strikethrough) => {
+                Node::Strikethrough(self.inner_nodes(strikethrough.inner.as_deref()))
+            }
+            Inner::Spoiler(spoiler) => Node::Spoiler(self.inner_nodes(spoiler.inner.as_deref())),
+            Inner::Blockquote(blockquote) => {
+                Node::Blockquote(self.inner_nodes(blockquote.inner.as_deref()))
+            }
+            Inner::InlineCode(code) => Node::InlineCode(code.text.clone()),
+            Inner::FencedCode(code) => Node::FencedCode {
+                info: code.info.clone(),
+                text: code.text.clone(),
+            },
+            Inner::Link(link) => Node::Link {
+                url: link.url.clone(),
+                content: self.inner_nodes(link.inner.as_deref()),
+            },
+            Inner::Heading(heading) => Node::Heading {
+                level: heading.level,
+                content: self.inner_nodes(heading.inner.as_deref()),
+            },
+            Inner::List(list) => {
+                let start = self.hints.list_starts.get(&self.seen.lists).copied();
+                self.seen.lists += 1;
+                let items = list
+                    .inner
+                    .iter()
+                    .map(|item| self.inner_nodes(Some(item)))
+                    .collect();
+                match start {
+                    Some(start) => Node::OrderedList { start, items },
+                    None => Node::List(items),
+                }
+            }
+            Inner::Timestamp(timestamp) => {
+                let style = self
+                    .hints
+                    .timestamp_styles
+                    .get(&self.seen.timestamps)
+                    .copied();
+                self.seen.timestamps += 1;
+                let time = timestamp.inner.unwrap_or_default().to_string();
+                Node::Timestamp(match style {
+                    Some(style) => TimestampNode::Styled { time, style },
+                    None => TimestampNode::Unstyled(time),
+                })
+            }
+        };
+
+        Some(node)
+    }
+}
+
+fn from_nodes<E: de::Error>(nodes: Vec<Node>) -> Result<Block, E> {
+    nodes.into_iter().try_fold(Block::new(), |block, node| {
+        Ok(block.append(from_node(node)?))
+    })
+}
+
+fn from_node<E: de::Error>(node: Node) -> Result<Block, E> {
+    let block = match node {
+        Node::Text(text) => Block::new().text(text),
+        Node::Container(nodes) => Block::new().container(
+            nodes
+                .into_iter()
+                .map(from_node)
+                .collect::<Result<Vec<_>, E>>()?,
+        ),
+        Node::Bold(nodes) => Block::new().bold(from_nodes(nodes)?),
+        Node::Italic(nodes) => Block::new().italic(from_nodes(nodes)?),
+        Node::Underline(nodes) => Block::new().underline(from_nodes(nodes)?),
+        Node::Strikethrough(nodes) => Block::new().strikethrough(from_nodes(nodes)?),
+        Node::Spoiler(nodes) => Block::new().spoiler(from_nodes(nodes)?),
+        Node::Blockquote(nodes) => Block::new().blockquote(from_nodes(nodes)?),
+        Node::InlineCode(text) => Block::new().inline_code(text),
+        Node::FencedCode { info, text } => Block::new().fenced_code(info, text),
+        Node::Link { url, content } => Block::new().link(url, from_nodes(content)?),
+        Node::Heading { level, content } => Block::new().heading(level, from_nodes(content)?),
+        Node::List(items) => Block::new().list(
+            items
+                .into_iter()
+                .map(from_nodes)
+                .collect::<Result<Vec<_>, E>>()?,
+        ),
+        Node::OrderedList { start, items } => Block::new().ordered_list(
+            start,
+            items
+                .into_iter()
+                .map(from_nodes)
+                .collect::<Result<Vec<_>, E>>()?,
+        ),
+        Node::Timestamp(TimestampNode::Unstyled(time)) => {
+            let timestamp: prost_types::Timestamp = time.parse().map_err(E::custom)?;
+            Block::from(proto::Block {
+                plain: String::new(),
+                inner: Some(proto::block::Inner::Timestamp(proto::TimestampBlock {
+                    inner: Some(timestamp),
+                })),
+            })
+        }
+        Node::Timestamp(TimestampNode::Styled { time, style }) => {
+            let timestamp: prost_types::Timestamp = time.parse().map_err(E::custom)?;
+            let time = SystemTime::try_from(timestamp).map_err(E::custom)?;
+            Block::new().styled_timestamp(time, style)
+        }
+    };
+
+    Ok(block)
+}
+
+impl Serialize for Block {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ToNodes::new(self.hints())
+            .nodes(self.children())
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Block {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_nodes(Vec::<Node>::deserialize(deserializer)?)
+    }
+}
+
+/// Serializes a [`proto::Block`] in the same format as [`Block`], for use with
+/// `#[serde(with = "...")]`.
+pub(crate) mod proto_block {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::block::{Block, Hints};
+    use crate::proto;
+
+    pub(crate) fn serialize<S: Serializer>(
+        block: &proto::Block,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::ToNodes::new(&Hints::default())
+            .inner_nodes(Some(block))
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<proto::Block, D::Error> {
+        Block::deserialize(deserializer).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::render::Dialect;
+    use crate::MessageContent;
+
+    fn every_node() -> Block {
+        Block::new()
+            .text("Hello ")
+            .bold("world")
+            .italic(Block::new().text("very ").underline("important"))
+            .strikethrough("old")
+            .spoiler("secret")
+            .blockquote("quoted")
+            .inline_code("x = 1")
+            .fenced_code("rust", "fn main() {}")
+            .fenced_code("", "plain code")
+            .link("https://example.com", "a link")
+            .heading(2, "Title")
+            .list(vec!["one", "two"])
+            .ordered_list(
+                3,
+                vec![Block::new().text("three").ordered_list(1, vec!["nested"])],
+            )
+            .timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .styled_timestamp(
+                UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+                TimestampStyle::ShortDate,
+            )
+            .container(vec![Block::new().text("a").bold("b")])
+    }
+
+    #[test]
+    fn serializes_as_tagged_nodes() {
+        let block = Block::new()
+            .text("Hello ")
+            .bold("world")
+            .link(
+                "https://example.com",
+                Block::new().text("a ").italic("link"),
+            )
+            .fenced_code("", "code")
+            .timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+        assert_eq!(
+            serde_json::to_value(&block).unwrap(),
+            json!([
+                { "text": "Hello " },
+                { "bold": [{ "text": "world" }] },
+                {
+                    "link": {
+                        "url": "https://example.com",
+                        "content": [{ "text": "a " }, { "italic": [{ "text": "link" }] }]
+                    }
+                },
+                { "fenced_code": { "text": "code" } },
+                { "timestamp": "2023-11-14T22:13:20Z" }
+            ])
+        );
+    }
+
+    #[test]
+    fn round_trips_every_node_type() {
+        let block = every_node();
+        let json = serde_json::to_string(&block).unwrap();
+        let parsed: Block = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            parsed.render(Dialect::Markdown),
+            block.render(Dialect::Markdown)
+        );
+        assert_eq!(proto::Block::from(parsed), proto::Block::from(block));
+    }
+
+    #[test]
+    fn serializes_timestamp_styles() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let block = Block::new()
+            .timestamp(time)
+            .styled_timestamp(time, TimestampStyle::Relative);
+
+        let value = serde_json::to_value(&block).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                { "timestamp": "2023-11-14T22:13:20Z" },
+                { "timestamp": { "time": "2023-11-14T22:13:20Z", "style": "relative" } }
+            ])
+        );
+
+        let parsed: Block = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            parsed.render(Dialect::Discord),
+            "<t:1700000000><t:1700000000:R>"
+        );
+    }
+
+    #[test]
+    fn serializes_ordered_lists() {
+        let block = Block::new()
+            .list(vec!["a"])
+            .bold(Block::new().ordered_list(2, vec!["b"]));
+
+        let value = serde_json::to_value(&block).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                { "list": [[{ "text": "a" }]] },
+                { "bold": [{ "ordered_list": { "start": 2, "items": [[{ "text": "b" }]] } }] }
+            ])
+        );
+
+        let parsed: Block = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.render(Dialect::Plain), "- a\n2. b");
+    }
+
+    #[test]
+    fn round_trips_message_content() {
+        let text = MessageContent::Text("hi".to_string());
+        assert_eq!(
+            serde_json::to_value(&text).unwrap(),
+            json!({ "text": "hi" })
+        );
+
+        let blocks = MessageContent::from(Block::new().text("hi ").bold("there"));
+        let value = serde_json::to_value(&blocks).unwrap();
+        assert_eq!(
+            value,
+            json!({ "blocks": [{ "text": "hi " }, { "bold": [{ "text": "there" }] }] })
+        );
+
+        match serde_json::from_value(value).unwrap() {
+            MessageContent::Blocks(parsed) => {
+                assert_eq!(
+                    parsed,
+                    proto::Block::from(Block::new().text("hi ").bold("there"))
+                )
+            }
+            MessageContent::Text(_) => panic!("expected blocks"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(serde_json::from_value::<Block>(json!([{ "timestamp": "yesterday" }])).is_err());
+        assert!(serde_json::from_value::<Block>(json!([{ "blink": "hi" }])).is_err());
+        assert!(serde_json::from_value::<Block>(json!({ "text": "not a list" })).is_err());
+    }
+}
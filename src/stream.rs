@@ -0,0 +1,168 @@
+use crate::error::Result;
+#[cfg(feature = "seabird-client")]
+use crate::info::InfoCache;
+use crate::proto;
+#[cfg(feature = "replay")]
+use crate::replay::{EventRecorder, Replay, ReplaySpeed};
+#[cfg(feature = "seabird-client")]
+use crate::telemetry;
+use crate::telemetry::StreamGuard;
+
+/// A stream of events from a seabird instance.
+///
+/// This is returned by [`SeabirdClient::stream_events`](crate::SeabirdClient::stream_events)
+/// and yields events in the order the server sends them. With the `replay`
+/// feature, it can also replay events from a recording. See the
+/// [`replay`](crate::replay) module.
+#[cfg(feature = "seabird-client")]
+#[derive(Debug)]
+pub struct EventStream {
+    source: EventSource,
+    cache: Option<InfoCache>,
+    #[cfg(feature = "replay")]
+    recorder: Option<EventRecorder>,
+}
+
+#[cfg(feature = "seabird-client")]
+#[derive(Debug)]
+enum EventSource {
+    Live {
+        inner: Box<tonic::Streaming<proto::Event>>,
+        guard: StreamGuard,
+    },
+    #[cfg(feature = "replay")]
+    Replay(Replay),
+}
+
+#[cfg(feature = "seabird-client")]
+impl EventStream {
+    pub(crate) fn new(inner: tonic::Streaming<proto::Event>, cache: Option<InfoCache>) -> Self {
+        Self {
+            cache,
+            source: EventSource::Live {
+                inner: Box::new(inner),
+                guard: StreamGuard::new("seabird"),
+            },
+            #[cfg(feature = "replay")]
+            recorder: None,
+        }
+    }
+
+    /// Opens a recording made with an [`EventRecorder`], to replay its
+    /// events at the given speed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be opened.
+    #[cfg(feature = "replay")]
+    pub async fn replay(path: impl AsRef<std::path::Path>, speed: ReplaySpeed) -> Result<Self> {
+        Ok(Self {
+            source: EventSource::Replay(Replay::open(path.as_ref(), speed).await?),
+            cache: None,
+            recorder: None,
+        })
+    }
+
+    /// Replays the events in a recording read from the given reader, at the
+    /// given speed.
+    #[cfg(feature = "replay")]
+    pub fn replay_from(
+        reader: impl tokio::io::AsyncBufRead + Send + 'static,
+        speed: ReplaySpeed,
+    ) -> Self {
+        Self {
+            source: EventSource::Replay(Replay::new(reader, speed)),
+            cache: None,
+            recorder: None,
+        }
+    }
+
+    /// Records every event yielded by this stream to the given recorder.
+    #[cfg(feature = "replay")]
+    pub fn record_to(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Waits for the next event on the stream.
+    ///
+    /// Returns `Ok(None)` once the server has closed the stream, or at the
+    /// end of a recording.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying gRPC stream fails, or if a
+    /// recording can't be read or written.
+    pub async fn next(&mut self) -> Result<Option<proto::Event>> {
+        let event = match &mut self.source {
+            EventSource::Live { inner, guard } => match inner.message().await {
+                Ok(Some(event)) => {
+                    telemetry::record_event(&event);
+                    if let Some(cache) = &self.cache {
+                        cache.observe(&event);
+                    }
+                    Some(event)
+                }
+                Ok(None) => {
+                    guard.disconnected();
+                    None
+                }
+                Err(err) => {
+                    guard.disconnected();
+                    return Err(err.into());
+                }
+            },
+            #[cfg(feature = "replay")]
+            EventSource::Replay(replay) => replay.next().await?,
+        };
+
+        #[cfg(feature = "replay")]
+        if let (Some(recorder), Some(event)) = (&mut self.recorder, &event) {
+            recorder.record(event).await?;
+        }
+
+        Ok(event)
+    }
+}
+
+/// A stream of requests from a seabird instance to a chat backend.
+///
+/// This is returned by [`ChatIngestClient::ingest_events`](crate::ChatIngestClient::ingest_events)
+/// and yields requests in the order the server sends them.
+#[cfg(feature = "chat-ingest-client")]
+#[derive(Debug)]
+pub struct ChatRequestStream {
+    inner: tonic::Streaming<proto::ChatRequest>,
+    guard: StreamGuard,
+}
+
+#[cfg(feature = "chat-ingest-client")]
+impl ChatRequestStream {
+    pub(crate) fn new(inner: tonic::Streaming<proto::ChatRequest>) -> Self {
+        Self {
+            inner,
+            guard: StreamGuard::new("chat_ingest"),
+        }
+    }
+
+    /// Waits for the next request on the stream.
+    ///
+    /// Returns `Ok(None)` once the server has closed the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying gRPC stream fails.
+    pub async fn next(&mut self) -> Result<Option<proto::ChatRequest>> {
+        match self.inner.message().await {
+            Ok(Some(request)) => Ok(Some(request)),
+            Ok(None) => {
+                self.guard.disconnected();
+                Ok(None)
+            }
+            Err(err) => {
+                self.guard.disconnected();
+                Err(err.into())
+            }
+        }
+    }
+}
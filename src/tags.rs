@@ -0,0 +1,162 @@
+//! Message tags.
+//!
+//! Tags are string key/value pairs sent alongside a message, which backends
+//! and other clients can use to change how it's handled. [`Tags`] builds them
+//! with methods for the well-known keys below, so they don't need to be
+//! spelled out by hand.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use seabird::tags::{self, Tags};
+//!
+//! let tags = Tags::new().reply_to("message-id").silent();
+//! assert_eq!(tags.get(tags::REPLY_TO), Some("message-id"));
+//!
+//! // Tags convert into the map taken by the send methods.
+//! let map: HashMap<String, String> = tags.into();
+//! assert_eq!(map.get(tags::SILENT).map(String::as_str), Some("true"));
+//! ```
+
+use std::collections::HashMap;
+
+/// The ID of the message this message replies to.
+pub const REPLY_TO: &str = "reply_to";
+
+/// The ID of the thread to send this message in.
+pub const THREAD_ID: &str = "thread_id";
+
+/// Set to `true` to send this message without notifying anyone.
+pub const SILENT: &str = "silent";
+
+/// A key which identifies this message, so that if it's sent more than once,
+/// such as when retrying after an error, it's only delivered once.
+pub const IDEMPOTENCY_KEY: &str = "idempotency_key";
+
+/// A set of message tags.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tags {
+    tags: HashMap<String, String>,
+}
+
+impl Tags {
+    /// Creates an empty set of tags.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a tag, replacing any existing value.
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Marks the message as a reply to another message. See [`REPLY_TO`].
+    pub fn reply_to(self, message_id: impl Into<String>) -> Self {
+        self.tag(REPLY_TO, message_id)
+    }
+
+    /// Sends the message in a thread. See [`THREAD_ID`].
+    pub fn thread_id(self, thread_id: impl Into<String>) -> Self {
+        self.tag(THREAD_ID, thread_id)
+    }
+
+    /// Sends the message without notifying anyone. See [`SILENT`].
+    pub fn silent(self) -> Self {
+        self.tag(SILENT, "true")
+    }
+
+    /// Makes sending the message idempotent. See [`IDEMPOTENCY_KEY`].
+    pub fn idempotency_key(self, key: impl Into<String>) -> Self {
+        self.tag(IDEMPOTENCY_KEY, key)
+    }
+
+    /// Returns the value of a tag.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.tags.get(key).map(String::as_str)
+    }
+
+    /// Returns whether there are no tags.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Returns the number of tags.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns an iterator over the tags, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+impl From<HashMap<String, String>> for Tags {
+    fn from(tags: HashMap<String, String>) -> Self {
+        Self { tags }
+    }
+}
+
+impl From<Tags> for HashMap<String, String> {
+    fn from(tags: Tags) -> Self {
+        tags.tags
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Tags {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            tags: iter
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn well_known_tags() {
+        let tags = Tags::new()
+            .reply_to("message-1")
+            .thread_id("thread-1")
+            .silent()
+            .idempotency_key("key-1");
+
+        assert_eq!(tags.len(), 4);
+        assert_eq!(tags.get(REPLY_TO), Some("message-1"));
+        assert_eq!(tags.get(THREAD_ID), Some("thread-1"));
+        assert_eq!(tags.get(SILENT), Some("true"));
+        assert_eq!(tags.get(IDEMPOTENCY_KEY), Some("key-1"));
+        assert_eq!(tags.get("missing"), None);
+    }
+
+    #[test]
+    fn later_values_replace_earlier_ones() {
+        let tags = Tags::new().reply_to("first").tag(REPLY_TO, "second");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags.get(REPLY_TO), Some("second"));
+    }
+
+    #[test]
+    fn conversions() {
+        assert!(Tags::new().is_empty());
+
+        let tags: Tags = [("a", "1"), ("b", "2")].into_iter().collect();
+        let mut pairs: Vec<_> = tags.iter().collect();
+        pairs.sort();
+        assert_eq!(pairs, [("a", "1"), ("b", "2")]);
+
+        let map: HashMap<String, String> = tags.clone().into();
+        assert_eq!(map.len(), 2);
+        assert_eq!(Tags::from(map), tags);
+    }
+}
@@ -0,0 +1,459 @@
+//! Internal helpers for the optional `tracing`, `opentelemetry` and `metrics`
+//! features.
+//!
+//! Every function in this module is a no-op when the corresponding feature is
+//! disabled, so call sites in the clients don't need their own `cfg` guards.
+
+#[cfg(feature = "seabird-client")]
+use std::collections::HashMap;
+
+use std::time::Instant;
+
+use tonic::metadata::MetadataMap;
+
+#[cfg(feature = "seabird-client")]
+use crate::proto;
+
+/// Records the target of an RPC (a channel or user ID) and the keys of any
+/// message tags on the current span.
+#[cfg(feature = "seabird-client")]
+#[allow(unused_variables)]
+pub(crate) fn record_target(target_id: &str, tags: &HashMap<String, String>) {
+    record_target_id(target_id);
+
+    #[cfg(feature = "tracing")]
+    {
+        let mut tag_keys: Vec<&str> = tags.keys().map(String::as_str).collect();
+        tag_keys.sort_unstable();
+
+        tracing::Span::current().record("tag_keys", tracing::field::debug(&tag_keys));
+    }
+}
+
+/// Records the target of an RPC which doesn't take message tags, such as a
+/// channel or backend ID, on the current span.
+#[cfg(feature = "seabird-client")]
+#[allow(unused_variables)]
+pub(crate) fn record_target_id(target_id: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("target_id", target_id);
+}
+
+/// Records the outcome of a finished RPC.
+///
+/// With the `tracing` feature this sets the gRPC status on the current span,
+/// and with the `metrics` feature it updates the request, error and latency
+/// metrics for the given service and method.
+#[allow(unused_variables)]
+pub(crate) fn record_response<T>(
+    service: &'static str,
+    method: &'static str,
+    started: Instant,
+    result: &std::result::Result<T, tonic::Status>,
+) {
+    let code = match result {
+        Ok(_) => tonic::Code::Ok,
+        Err(status) => status.code(),
+    };
+
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        span.record("grpc.status", tracing::field::debug(code));
+        if let Err(status) = result {
+            tracing::warn!(message = status.message(), "seabird request failed");
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!(
+            "seabird_client_requests_total",
+            "service" => service,
+            "method" => method,
+        )
+        .increment(1);
+        metrics::histogram!(
+            "seabird_client_request_duration_seconds",
+            "service" => service,
+            "method" => method,
+        )
+        .record(started.elapsed().as_secs_f64());
+
+        if code != tonic::Code::Ok {
+            metrics::counter!(
+                "seabird_client_errors_total",
+                "service" => service,
+                "method" => method,
+                "code" => format!("{:?}", code),
+            )
+            .increment(1);
+        }
+    }
+}
+
+/// Tracks whether a stream is connected.
+///
+/// With the `metrics` feature, the `seabird_client_streams_connected` gauge
+/// is incremented when the guard is created and decremented once the stream
+/// ends, fails or is dropped.
+#[derive(Debug)]
+pub(crate) struct StreamGuard {
+    #[cfg(feature = "metrics")]
+    service: &'static str,
+    #[cfg(feature = "metrics")]
+    connected: bool,
+}
+
+impl StreamGuard {
+    #[allow(unused_variables)]
+    pub(crate) fn new(service: &'static str) -> Self {
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("seabird_client_streams_connected", "service" => service).increment(1.0);
+
+        Self {
+            #[cfg(feature = "metrics")]
+            service,
+            #[cfg(feature = "metrics")]
+            connected: true,
+        }
+    }
+
+    /// Marks the stream as disconnected. Calling this more than once has no
+    /// further effect.
+    pub(crate) fn disconnected(&mut self) {
+        #[cfg(feature = "metrics")]
+        if self.connected {
+            self.connected = false;
+            metrics::gauge!("seabird_client_streams_connected", "service" => self.service)
+                .decrement(1.0);
+        }
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.disconnected();
+    }
+}
+
+/// Records an event received on the event stream, as a debug event with the
+/// `tracing` feature and in the per-type event counter with `metrics`.
+#[cfg(feature = "seabird-client")]
+#[allow(unused_variables)]
+pub(crate) fn record_event(event: &proto::Event) {
+    #[cfg(feature = "tracing")]
+    {
+        let mut tag_keys: Vec<&str> = event.tags.keys().map(String::as_str).collect();
+        tag_keys.sort_unstable();
+
+        tracing::debug!(
+            event_type = event_type(event),
+            tag_keys = ?tag_keys,
+            "received seabird event"
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!(
+        "seabird_client_events_received_total",
+        "event_type" => event_type(event),
+    )
+    .increment(1);
+}
+
+/// Records a bot handler which returned an error, as a warning with the
+/// `tracing` feature and in the per-handler error counter with `metrics`.
+#[cfg(feature = "bot")]
+#[allow(unused_variables)]
+pub(crate) fn record_handler_error(handler: &str, err: &crate::error::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(handler, error = %err, "seabird handler failed");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!(
+        "seabird_bot_handler_errors_total",
+        "handler" => handler.to_string(),
+    )
+    .increment(1);
+}
+
+/// Records a command stopped by a middleware check, as a debug event with the
+/// `tracing` feature and in the per-command denial counter with `metrics`.
+#[cfg(feature = "bot")]
+#[allow(unused_variables)]
+pub(crate) fn record_command_denied(command: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(command, "seabird command denied");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!(
+        "seabird_bot_commands_denied_total",
+        "command" => command.to_string(),
+    )
+    .increment(1);
+}
+
+/// Records bot handlers cancelled because they didn't finish before the
+/// drain timeout, as a warning with the `tracing` feature and in the
+/// cancelled handler counter with `metrics`.
+#[cfg(feature = "bot")]
+#[allow(unused_variables)]
+pub(crate) fn record_handlers_cancelled(count: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(count, "seabird handlers cancelled at shutdown");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!("seabird_bot_handlers_cancelled_total").increment(count as u64);
+}
+
+/// Records a scheduled job which failed to send or be stored, as a warning
+/// with the `tracing` feature and in the job error counter with `metrics`.
+#[cfg(feature = "scheduler")]
+#[allow(unused_variables)]
+pub(crate) fn record_job_error(job_id: u64, err: &crate::error::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(job_id, error = %err, "seabird scheduled job failed");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!("seabird_scheduler_job_errors_total").increment(1);
+}
+
+/// Returns a short, stable name for the type of the given event.
+#[cfg(all(
+    feature = "seabird-client",
+    any(feature = "tracing", feature = "metrics")
+))]
+pub(crate) fn event_type(event: &proto::Event) -> &'static str {
+    use proto::event::Inner;
+
+    match &event.inner {
+        Some(Inner::Message(_)) => "message",
+        Some(Inner::PrivateMessage(_)) => "private_message",
+        Some(Inner::Mention(_)) => "mention",
+        Some(Inner::Command(_)) => "command",
+        Some(Inner::Action(_)) => "action",
+        Some(Inner::PrivateAction(_)) => "private_action",
+        Some(Inner::SendMessage(_)) => "send_message",
+        Some(Inner::SendPrivateMessage(_)) => "send_private_message",
+        Some(Inner::PerformAction(_)) => "perform_action",
+        Some(Inner::PerformPrivateAction(_)) => "perform_private_action",
+        None => "unknown",
+    }
+}
+
+/// Injects the context of the current span into outgoing request metadata,
+/// using the globally configured OpenTelemetry propagator.
+///
+/// Nothing is injected until a propagator is installed with
+/// `opentelemetry::global::set_text_map_propagator`, such as the SDK's
+/// `TraceContextPropagator` for W3C trace context, possibly combined with
+/// others for baggage.
+#[allow(unused_variables)]
+pub(crate) fn inject_trace_context(metadata: &mut MetadataMap) {
+    #[cfg(feature = "opentelemetry")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let context = tracing::Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut MetadataInjector(metadata));
+        });
+    }
+}
+
+/// Adapts request metadata for OpenTelemetry propagators. Fields which
+/// aren't valid metadata are skipped.
+#[cfg(feature = "opentelemetry")]
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        let key = tonic::metadata::MetadataKey::from_bytes(key.as_bytes());
+        if let (Ok(key), Ok(value)) = (key, value.parse()) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "opentelemetry"))]
+mod tests {
+    use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+    use opentelemetry::Context;
+
+    use super::*;
+
+    /// A propagator which injects fixed fields, standing in for a configured
+    /// trace context and baggage propagator.
+    #[derive(Debug)]
+    struct FixedPropagator;
+
+    impl TextMapPropagator for FixedPropagator {
+        fn inject_context(&self, _cx: &Context, injector: &mut dyn Injector) {
+            injector.set(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string(),
+            );
+            injector.set("baggage", "user=alice".to_string());
+            injector.set("invalid key", "skipped".to_string());
+        }
+
+        fn extract_with_context(&self, cx: &Context, _extractor: &dyn Extractor) -> Context {
+            cx.clone()
+        }
+
+        fn fields(&self) -> opentelemetry::propagation::text_map_propagator::FieldIter<'_> {
+            opentelemetry::propagation::text_map_propagator::FieldIter::new(&[])
+        }
+    }
+
+    #[test]
+    fn injects_fields_from_the_global_propagator() {
+        opentelemetry::global::set_text_map_propagator(FixedPropagator);
+
+        let mut metadata = MetadataMap::new();
+        inject_trace_context(&mut metadata);
+
+        assert_eq!(
+            metadata
+                .get("traceparent")
+                .and_then(|value| value.to_str().ok()),
+            Some("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01")
+        );
+        assert_eq!(
+            metadata
+                .get("baggage")
+                .and_then(|value| value.to_str().ok()),
+            Some("user=alice")
+        );
+        assert_eq!(metadata.len(), 2);
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod metrics_tests {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+    use metrics_util::MetricKind;
+
+    use super::*;
+
+    type Metric = (Vec<String>, DebugValue);
+
+    /// Takes a snapshot of all metrics, keyed by kind and name. Taking a
+    /// snapshot resets counters and gauges, so each one only reflects changes
+    /// since the previous snapshot.
+    fn snapshot(snapshotter: &Snapshotter) -> Vec<(MetricKind, String, Metric)> {
+        snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let labels = key
+                    .key()
+                    .labels()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect();
+                (key.kind(), key.key().name().to_string(), (labels, value))
+            })
+            .collect()
+    }
+
+    /// Removes and returns the metrics of the given kind and name.
+    fn find(
+        metrics: &mut Vec<(MetricKind, String, Metric)>,
+        kind: MetricKind,
+        name: &str,
+    ) -> Vec<Metric> {
+        metrics
+            .extract_if(.., |(k, n, _)| *k == kind && n == name)
+            .map(|(_, _, metric)| metric)
+            .collect()
+    }
+
+    #[test]
+    fn records_requests_and_errors() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let ok: std::result::Result<(), tonic::Status> = Ok(());
+            let err: std::result::Result<(), tonic::Status> =
+                Err(tonic::Status::not_found("no such channel"));
+            record_response("seabird", "get_channel_info", Instant::now(), &ok);
+            record_response("seabird", "get_channel_info", Instant::now(), &err);
+        });
+
+        let mut metrics = snapshot(&snapshotter);
+        assert_eq!(
+            find(
+                &mut metrics,
+                MetricKind::Counter,
+                "seabird_client_requests_total"
+            ),
+            vec![(
+                vec![
+                    "service=seabird".to_string(),
+                    "method=get_channel_info".to_string()
+                ],
+                DebugValue::Counter(2)
+            )]
+        );
+        assert_eq!(
+            find(
+                &mut metrics,
+                MetricKind::Counter,
+                "seabird_client_errors_total"
+            ),
+            vec![(
+                vec![
+                    "service=seabird".to_string(),
+                    "method=get_channel_info".to_string(),
+                    "code=NotFound".to_string()
+                ],
+                DebugValue::Counter(1)
+            )]
+        );
+
+        let durations = find(
+            &mut metrics,
+            MetricKind::Histogram,
+            "seabird_client_request_duration_seconds",
+        );
+        assert!(
+            matches!(&durations[..], [(_, DebugValue::Histogram(values))] if values.len() == 2)
+        );
+    }
+
+    #[test]
+    fn stream_guard_tracks_connected_streams() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let change = || {
+            let mut metrics = snapshot(&snapshotter);
+            match &find(
+                &mut metrics,
+                MetricKind::Gauge,
+                "seabird_client_streams_connected",
+            )[..]
+            {
+                [(_, DebugValue::Gauge(value))] => value.into_inner(),
+                other => panic!("unexpected gauge values: {other:?}"),
+            }
+        };
+
+        metrics::with_local_recorder(&recorder, || {
+            let mut first = StreamGuard::new("seabird");
+            let second = StreamGuard::new("seabird");
+            assert_eq!(change(), 2.0);
+
+            first.disconnected();
+            first.disconnected();
+            assert_eq!(change(), -1.0);
+
+            drop(second);
+            drop(first);
+            assert_eq!(change(), -1.0);
+        });
+    }
+}
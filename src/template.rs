@@ -0,0 +1,489 @@
+//! Templates for building blocks with safely interpolated arguments.
+//!
+//! Building a message by formatting untrusted input into Markdown allows that
+//! input to inject its own formatting. Templates avoid this by only parsing
+//! formatting from the template itself; arguments are always inserted as
+//! plain text blocks, no matter what characters they contain.
+//!
+//! The template syntax is a small subset of Markdown:
+//!
+//! - `**bold**`
+//! - `*italic*` or `_italic_`
+//! - `__underline__`
+//! - `~~strikethrough~~`
+//! - `||spoiler||`
+//! - `` `inline code` ``
+//! - `{name}` to insert the argument called `name`
+//!
+//! Formatting can be nested, and placeholders can be used anywhere, including
+//! in inline code. Any special character can be escaped with a backslash, and
+//! `{{` and `}}` insert literal braces. As in Markdown, `_` and `__` inside a
+//! word (with a letter or digit on both sides, such as in `user_id`) are left
+//! as they are.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use seabird::block;
+//! use seabird::render::Dialect;
+//!
+//! let name = "**not bold**";
+//! let block = block!("Hello **{name}**, you have {count} points", name, count = 42)?;
+//!
+//! assert_eq!(
+//!     block.render(Dialect::Plain),
+//!     "Hello **not bold**, you have 42 points"
+//! );
+//! # Ok::<(), seabird::template::TemplateError>(())
+//! ```
+
+use std::fmt;
+
+use crate::block::Block;
+
+/// Builds a [`Block`] from a template, inserting each argument as plain text.
+///
+/// Arguments are given by name, either as `name = value` or as a variable
+/// called `name`. Values can be anything which implements
+/// [`Display`](std::fmt::Display). See the [`template`](crate::template)
+/// module for the template syntax.
+///
+/// # Errors
+///
+/// Returns a [`TemplateError`](crate::template::TemplateError) if the template
+/// is malformed or refers to an argument which wasn't given.
+#[macro_export]
+macro_rules! block {
+    (@value $name:ident = $value:expr) => {
+        $value
+    };
+    (@value $name:ident) => {
+        $name
+    };
+    ($template:expr $(, $name:ident $(= $value:expr)?)* $(,)?) => {
+        $crate::template::Template::parse($template).and_then(|template| {
+            template.render(&[$((
+                stringify!($name),
+                &$crate::block!(@value $name $(= $value)?) as &dyn ::std::fmt::Display,
+            )),*])
+        })
+    };
+}
+
+/// An error from parsing or rendering a template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    /// Formatting which was opened but never closed.
+    UnclosedFormatting { marker: &'static str },
+    /// A `{` without a matching `}`.
+    UnclosedPlaceholder,
+    /// A `}` without a matching `{`.
+    UnmatchedBrace,
+    /// A placeholder which isn't a valid argument name.
+    InvalidPlaceholder { name: String },
+    /// A backslash at the end of the template.
+    TrailingBackslash,
+    /// A placeholder for an argument which wasn't given.
+    MissingArgument { name: String },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnclosedFormatting { marker } => {
+                write!(f, "unclosed {:?} in template", marker)
+            }
+            TemplateError::UnclosedPlaceholder => write!(f, "unclosed '{{' in template"),
+            TemplateError::UnmatchedBrace => write!(f, "unmatched '}}' in template"),
+            TemplateError::InvalidPlaceholder { name } => {
+                write!(f, "invalid placeholder {{{}}} in template", name)
+            }
+            TemplateError::TrailingBackslash => write!(f, "trailing backslash in template"),
+            TemplateError::MissingArgument { name } => {
+                write!(f, "missing template argument {:?}", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Spoiler,
+}
+
+// Markers are matched in order, so longer markers must come before any
+// markers which are a prefix of them.
+const MARKERS: [(&str, Format); 6] = [
+    ("**", Format::Bold),
+    ("__", Format::Underline),
+    ("~~", Format::Strikethrough),
+    ("||", Format::Spoiler),
+    ("*", Format::Italic),
+    ("_", Format::Italic),
+];
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Text(String),
+    Placeholder(String),
+    Code(Vec<Segment>),
+    Formatted(Format, Vec<Segment>),
+}
+
+/// A parsed template, which can be rendered many times with different
+/// arguments.
+#[derive(Clone, Debug)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parses a template.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template has unclosed formatting or
+    /// placeholders, or a placeholder which isn't a valid name.
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut parser = Parser {
+            template,
+            rest: template,
+            stack: vec![(None, Vec::new())],
+            text: String::new(),
+        };
+        parser.parse()?;
+
+        Ok(Self {
+            segments: parser.stack.pop().unwrap().1,
+        })
+    }
+
+    /// Renders the template, inserting each argument as a plain text block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template refers to an argument which isn't in
+    /// `args`.
+    pub fn render(&self, args: &[(&str, &dyn fmt::Display)]) -> Result<Block, TemplateError> {
+        render_segments(&self.segments, args)
+    }
+}
+
+struct Parser<'t> {
+    template: &'t str,
+    rest: &'t str,
+    /// The formatting blocks which are currently open, each with the marker
+    /// which opened it and the segments inside it. The first entry is the top
+    /// level of the template.
+    stack: Vec<(Option<&'static str>, Vec<Segment>)>,
+    /// Text which hasn't been added to a segment yet.
+    text: String,
+}
+
+impl Parser<'_> {
+    fn flush_text(&mut self) {
+        if !self.text.is_empty() {
+            let text = std::mem::take(&mut self.text);
+            self.stack.last_mut().unwrap().1.push(Segment::Text(text));
+        }
+    }
+
+    fn parse(&mut self) -> Result<(), TemplateError> {
+        while let Some(c) = self.rest.chars().next() {
+            if let Some(rest) = self.rest.strip_prefix("{{") {
+                self.text.push('{');
+                self.rest = rest;
+            } else if let Some(rest) = self.rest.strip_prefix("}}") {
+                self.text.push('}');
+                self.rest = rest;
+            } else if c == '{' {
+                let name = self.placeholder()?;
+                self.flush_text();
+                self.stack
+                    .last_mut()
+                    .unwrap()
+                    .1
+                    .push(Segment::Placeholder(name));
+            } else if c == '}' {
+                return Err(TemplateError::UnmatchedBrace);
+            } else if c == '\\' {
+                let mut chars = self.rest[1..].chars();
+                let escaped = chars.next().ok_or(TemplateError::TrailingBackslash)?;
+                self.text.push(escaped);
+                self.rest = chars.as_str();
+            } else if c == '`' {
+                self.rest = &self.rest[1..];
+                self.code()?;
+            } else if let Some((marker, format)) = MARKERS
+                .iter()
+                .find(|(marker, _)| self.rest.starts_with(marker))
+            {
+                let intraword = self.is_intraword(marker);
+                self.rest = &self.rest[marker.len()..];
+                if intraword {
+                    self.text.push_str(marker);
+                } else {
+                    self.marker(marker, *format);
+                }
+            } else {
+                self.text.push(c);
+                self.rest = &self.rest[c.len_utf8()..];
+            }
+        }
+
+        self.flush_text();
+        match self.stack.last() {
+            Some((Some(marker), _)) => Err(TemplateError::UnclosedFormatting { marker }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns true if the given marker at the start of the remaining input is
+    /// an underscore marker inside a word, which is treated as text.
+    fn is_intraword(&self, marker: &str) -> bool {
+        let before = &self.template[..self.template.len() - self.rest.len()];
+        marker.starts_with('_')
+            && before
+                .chars()
+                .next_back()
+                .is_some_and(char::is_alphanumeric)
+            && self.rest[marker.len()..]
+                .chars()
+                .next()
+                .is_some_and(char::is_alphanumeric)
+    }
+
+    /// Parses a `{name}` placeholder at the start of the remaining input.
+    fn placeholder(&mut self) -> Result<String, TemplateError> {
+        let end = self
+            .rest
+            .find('}')
+            .ok_or(TemplateError::UnclosedPlaceholder)?;
+        let name = self.rest[1..end].trim();
+        let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !valid {
+            return Err(TemplateError::InvalidPlaceholder {
+                name: name.to_string(),
+            });
+        }
+
+        self.rest = &self.rest[end + 1..];
+        Ok(name.to_string())
+    }
+
+    /// Opens or closes formatting for the given marker.
+    fn marker(&mut self, marker: &'static str, format: Format) {
+        self.flush_text();
+        if matches!(self.stack.last(), Some((Some(open), _)) if *open == marker) {
+            let (_, segments) = self.stack.pop().unwrap();
+            self.stack
+                .last_mut()
+                .unwrap()
+                .1
+                .push(Segment::Formatted(format, segments));
+        } else {
+            self.stack.push((Some(marker), Vec::new()));
+        }
+    }
+
+    /// Parses inline code up to the closing backtick. Only placeholders and
+    /// escapes are handled inside code.
+    fn code(&mut self) -> Result<(), TemplateError> {
+        self.flush_text();
+        let mut segments = Vec::new();
+        loop {
+            let Some(c) = self.rest.chars().next() else {
+                return Err(TemplateError::UnclosedFormatting { marker: "`" });
+            };
+
+            if c == '`' {
+                self.rest = &self.rest[1..];
+                break;
+            } else if let Some(rest) = self.rest.strip_prefix("{{") {
+                self.text.push('{');
+                self.rest = rest;
+            } else if let Some(rest) = self.rest.strip_prefix("}}") {
+                self.text.push('}');
+                self.rest = rest;
+            } else if c == '{' {
+                let name = self.placeholder()?;
+                if !self.text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut self.text)));
+                }
+                segments.push(Segment::Placeholder(name));
+            } else if c == '\\' {
+                let mut chars = self.rest[1..].chars();
+                let escaped = chars.next().ok_or(TemplateError::TrailingBackslash)?;
+                self.text.push(escaped);
+                self.rest = chars.as_str();
+            } else if c == '}' {
+                return Err(TemplateError::UnmatchedBrace);
+            } else {
+                self.text.push(c);
+                self.rest = &self.rest[c.len_utf8()..];
+            }
+        }
+
+        if !self.text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut self.text)));
+        }
+        self.stack
+            .last_mut()
+            .unwrap()
+            .1
+            .push(Segment::Code(segments));
+        Ok(())
+    }
+}
+
+fn lookup(name: &str, args: &[(&str, &dyn fmt::Display)]) -> Result<String, TemplateError> {
+    args.iter()
+        .find(|(arg, _)| *arg == name)
+        .map(|(_, value)| value.to_string())
+        .ok_or_else(|| TemplateError::MissingArgument {
+            name: name.to_string(),
+        })
+}
+
+fn render_segments(
+    segments: &[Segment],
+    args: &[(&str, &dyn fmt::Display)],
+) -> Result<Block, TemplateError> {
+    let mut block = Block::new();
+    for segment in segments {
+        block = match segment {
+            Segment::Text(text) => block.text(text.as_str()),
+            Segment::Placeholder(name) => block.text(lookup(name, args)?),
+            Segment::Code(segments) => {
+                let mut code = String::new();
+                for segment in segments {
+                    match segment {
+                        Segment::Placeholder(name) => code.push_str(&lookup(name, args)?),
+                        Segment::Text(text) => code.push_str(text),
+                        Segment::Code(_) | Segment::Formatted(..) => {}
+                    }
+                }
+                block.inline_code(code)
+            }
+            Segment::Formatted(format, segments) => {
+                let inner = render_segments(segments, args)?;
+                match format {
+                    Format::Bold => block.bold(inner),
+                    Format::Italic => block.italic(inner),
+                    Format::Underline => block.underline(inner),
+                    Format::Strikethrough => block.strikethrough(inner),
+                    Format::Spoiler => block.spoiler(inner),
+                }
+            }
+        };
+    }
+    Ok(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto;
+    use crate::render::Dialect;
+
+    fn markdown(block: Result<Block, TemplateError>) -> String {
+        block.unwrap().render(Dialect::Markdown)
+    }
+
+    #[test]
+    fn arguments_are_inserted_as_text() {
+        let name = "*evil* [link](https://example.com)";
+        let block = crate::block!("Hi **{name}**!", name).unwrap();
+
+        let expected = Block::new().text("Hi ").bold(name).text("!");
+        assert_eq!(proto::Block::from(block), proto::Block::from(expected));
+    }
+
+    #[test]
+    fn parses_formatting() {
+        assert_eq!(
+            markdown(crate::block!(
+                "**bold *italic* text** _also italic_ ~~gone~~ ||hidden||"
+            )),
+            "**bold *italic* text** *also italic* ~~gone~~ hidden"
+        );
+        assert_eq!(
+            crate::block!("__underline__")
+                .unwrap()
+                .render(Dialect::Discord),
+            "__underline__"
+        );
+    }
+
+    #[test]
+    fn underscores_inside_words_are_text() {
+        let id = 42;
+        assert_eq!(
+            crate::block!("user_id: {id}", id)
+                .unwrap()
+                .render(Dialect::Plain),
+            "user_id: 42"
+        );
+        assert_eq!(
+            markdown(crate::block!("snake__case and _italic_ and x_y_z")),
+            r"snake\_\_case and *italic* and x\_y\_z"
+        );
+        assert_eq!(markdown(crate::block!("a _b_c_ d")), r"a *b\_c* d");
+    }
+
+    #[test]
+    fn placeholders_work_in_code() {
+        let cmd = "rm `-rf`";
+        assert_eq!(
+            markdown(crate::block!("Run `{cmd} now`", cmd)),
+            "Run ``rm `-rf` now``"
+        );
+    }
+
+    #[test]
+    fn escapes_and_literal_braces() {
+        assert_eq!(
+            crate::block!(r"\*not italic\* {{{value}}}", value = 1)
+                .unwrap()
+                .render(Dialect::Plain),
+            "*not italic* {1}"
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = |template: &str| Template::parse(template).unwrap_err();
+
+        assert_eq!(
+            error("**open"),
+            TemplateError::UnclosedFormatting { marker: "**" }
+        );
+        assert_eq!(
+            error("`code"),
+            TemplateError::UnclosedFormatting { marker: "`" }
+        );
+        assert_eq!(error("{name"), TemplateError::UnclosedPlaceholder);
+        assert_eq!(error("name}"), TemplateError::UnmatchedBrace);
+        assert_eq!(
+            error("{not valid}"),
+            TemplateError::InvalidPlaceholder {
+                name: "not valid".to_string()
+            }
+        );
+        assert_eq!(error("trailing\\"), TemplateError::TrailingBackslash);
+
+        assert_eq!(
+            crate::block!("{missing}").unwrap_err(),
+            TemplateError::MissingArgument {
+                name: "missing".to_string()
+            }
+        );
+    }
+}
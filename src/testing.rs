@@ -0,0 +1,320 @@
+//! A seabird server for tests, which sends the events it's given and records
+//! the messages it receives.
+//!
+//! Sending a message to the channel [`UNAVAILABLE`] fails, for testing error
+//! handling.
+
+// Not every helper is used with every combination of features.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{mpsc, Notify};
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tonic::{Request, Response, Status};
+
+use crate::proto;
+use crate::proto::seabird::seabird_server::{Seabird, SeabirdServer};
+use crate::render::{self, Dialect};
+use crate::{ClientConfig, SeabirdClient};
+
+/// A channel which messages can't be sent to.
+pub(crate) const UNAVAILABLE: &str = "unavailable";
+
+/// A message received by the mock server, with its blocks rendered as plain
+/// text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Sent {
+    pub(crate) target_id: String,
+    pub(crate) text: String,
+    pub(crate) tags: HashMap<String, String>,
+}
+
+impl Sent {
+    fn new(
+        target_id: String,
+        text: String,
+        root_block: Option<proto::Block>,
+        tags: HashMap<String, String>,
+    ) -> Self {
+        let text = match root_block {
+            Some(block) => render::render(&block, Dialect::Plain),
+            None => text,
+        };
+        Self {
+            target_id,
+            text,
+            tags,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    commands: Mutex<HashMap<String, proto::CommandMetadata>>,
+    messages: Mutex<Vec<Sent>>,
+    private_messages: Mutex<Vec<Sent>>,
+    actions: Mutex<Vec<Sent>>,
+    private_actions: Mutex<Vec<Sent>>,
+    events: Mutex<Option<mpsc::UnboundedReceiver<Result<proto::Event, Status>>>>,
+    changed: Notify,
+}
+
+/// The test side of a mock seabird server.
+#[derive(Debug)]
+pub(crate) struct MockSeabird {
+    state: Arc<State>,
+    events: Option<mpsc::UnboundedSender<Result<proto::Event, Status>>>,
+}
+
+impl MockSeabird {
+    /// Starts a server on a local port and connects a client to it.
+    pub(crate) async fn start() -> (Self, SeabirdClient) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let state = Arc::new(State {
+            events: Mutex::new(Some(receiver)),
+            ..State::default()
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(SeabirdServer::new(Service(Arc::clone(&state))))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let client = SeabirdClient::new(ClientConfig {
+            url,
+            token: "token".to_string(),
+        })
+        .await
+        .unwrap();
+
+        let mock = Self {
+            state,
+            events: Some(sender),
+        };
+        (mock, client)
+    }
+
+    /// Sends an event on the event stream.
+    pub(crate) fn send(&self, event: proto::Event) {
+        if let Some(events) = &self.events {
+            let _ = events.send(Ok(event));
+        }
+    }
+
+    /// Closes the event stream once every event sent so far is received.
+    pub(crate) fn close(&mut self) {
+        self.events = None;
+    }
+
+    /// Returns the commands registered when the event stream was opened.
+    pub(crate) fn commands(&self) -> HashMap<String, proto::CommandMetadata> {
+        self.state.commands.lock().unwrap().clone()
+    }
+
+    /// Returns the messages sent to channels so far.
+    pub(crate) fn messages(&self) -> Vec<Sent> {
+        self.state.messages.lock().unwrap().clone()
+    }
+
+    /// Returns the private messages sent so far.
+    pub(crate) fn private_messages(&self) -> Vec<Sent> {
+        self.state.private_messages.lock().unwrap().clone()
+    }
+
+    /// Returns the actions performed in channels so far.
+    pub(crate) fn actions(&self) -> Vec<Sent> {
+        self.state.actions.lock().unwrap().clone()
+    }
+
+    /// Returns the private actions performed so far.
+    pub(crate) fn private_actions(&self) -> Vec<Sent> {
+        self.state.private_actions.lock().unwrap().clone()
+    }
+
+    /// Waits until at least `count` messages have been sent to channels,
+    /// failing the test if that takes more than a few seconds.
+    pub(crate) async fn wait_for_messages(&self, count: usize) -> Vec<Sent> {
+        let wait = async {
+            loop {
+                let changed = self.state.changed.notified();
+                let messages = self.messages();
+                if messages.len() >= count {
+                    return messages;
+                }
+                changed.await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .unwrap_or_else(|_| panic!("timed out waiting for {count} messages"))
+    }
+}
+
+#[derive(Debug)]
+struct Service(Arc<State>);
+
+#[tonic::async_trait]
+impl Seabird for Service {
+    type StreamEventsStream = UnboundedReceiverStream<Result<proto::Event, Status>>;
+
+    async fn stream_events(
+        &self,
+        request: Request<proto::StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        *self.0.commands.lock().unwrap() = request.into_inner().commands;
+        let events = self.0.events.lock().unwrap().take();
+        let events = events.ok_or_else(|| Status::already_exists("stream already open"))?;
+        Ok(Response::new(UnboundedReceiverStream::new(events)))
+    }
+
+    async fn perform_action(
+        &self,
+        request: Request<proto::PerformActionRequest>,
+    ) -> Result<Response<proto::PerformActionResponse>, Status> {
+        let request = request.into_inner();
+        let sent = Sent::new(
+            request.channel_id,
+            request.text,
+            request.root_block,
+            request.tags,
+        );
+        self.0.actions.lock().unwrap().push(sent);
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn perform_private_action(
+        &self,
+        request: Request<proto::PerformPrivateActionRequest>,
+    ) -> Result<Response<proto::PerformPrivateActionResponse>, Status> {
+        let request = request.into_inner();
+        let sent = Sent::new(
+            request.user_id,
+            request.text,
+            request.root_block,
+            request.tags,
+        );
+        self.0.private_actions.lock().unwrap().push(sent);
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn send_message(
+        &self,
+        request: Request<proto::SendMessageRequest>,
+    ) -> Result<Response<proto::SendMessageResponse>, Status> {
+        let request = request.into_inner();
+        if request.channel_id == UNAVAILABLE {
+            return Err(Status::unavailable("channel unavailable"));
+        }
+        let sent = Sent::new(
+            request.channel_id,
+            request.text,
+            request.root_block,
+            request.tags,
+        );
+        self.0.messages.lock().unwrap().push(sent);
+        self.0.changed.notify_waiters();
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn send_private_message(
+        &self,
+        request: Request<proto::SendPrivateMessageRequest>,
+    ) -> Result<Response<proto::SendPrivateMessageResponse>, Status> {
+        let request = request.into_inner();
+        let sent = Sent::new(
+            request.user_id,
+            request.text,
+            request.root_block,
+            request.tags,
+        );
+        self.0.private_messages.lock().unwrap().push(sent);
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn join_channel(
+        &self,
+        _request: Request<proto::JoinChannelRequest>,
+    ) -> Result<Response<proto::JoinChannelResponse>, Status> {
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn leave_channel(
+        &self,
+        _request: Request<proto::LeaveChannelRequest>,
+    ) -> Result<Response<proto::LeaveChannelResponse>, Status> {
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn update_channel_info(
+        &self,
+        _request: Request<proto::UpdateChannelInfoRequest>,
+    ) -> Result<Response<proto::UpdateChannelInfoResponse>, Status> {
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn list_backends(
+        &self,
+        _request: Request<proto::ListBackendsRequest>,
+    ) -> Result<Response<proto::ListBackendsResponse>, Status> {
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn get_backend_info(
+        &self,
+        _request: Request<proto::BackendInfoRequest>,
+    ) -> Result<Response<proto::BackendInfoResponse>, Status> {
+        Err(Status::not_found("no such backend"))
+    }
+
+    async fn list_channels(
+        &self,
+        _request: Request<proto::ListChannelsRequest>,
+    ) -> Result<Response<proto::ListChannelsResponse>, Status> {
+        Ok(Response::new(Default::default()))
+    }
+
+    async fn get_channel_info(
+        &self,
+        _request: Request<proto::ChannelInfoRequest>,
+    ) -> Result<Response<proto::ChannelInfoResponse>, Status> {
+        Err(Status::not_found("no such channel"))
+    }
+
+    async fn get_current_user(
+        &self,
+        _request: Request<proto::GetCurrentUserRequest>,
+    ) -> Result<Response<proto::GetCurrentUserResponse>, Status> {
+        Err(Status::not_found("no such backend"))
+    }
+
+    async fn get_core_info(
+        &self,
+        _request: Request<proto::CoreInfoRequest>,
+    ) -> Result<Response<proto::CoreInfoResponse>, Status> {
+        Ok(Response::new(Default::default()))
+    }
+}
+
+/// Builds a command event from a user in a channel.
+pub(crate) fn command_event(command: &str, arg: &str) -> proto::Event {
+    proto::Event {
+        tags: HashMap::new(),
+        inner: Some(proto::event::Inner::Command(proto::CommandEvent {
+            source: Some(proto::ChannelSource {
+                channel_id: "chan".to_string(),
+                user: Some(proto::User {
+                    id: "user-1".to_string(),
+                    display_name: "alice".to_string(),
+                }),
+            }),
+            command: command.to_string(),
+            arg: arg.to_string(),
+        })),
+    }
+}
@@ -0,0 +1,343 @@
+//! Cutting block trees down to a maximum rendered length.
+//!
+//! Truncation works on the block tree rather than the rendered text, so the
+//! renderer still closes any formatting around the cut. Text and code are cut
+//! between grapheme clusters, while links, mentions and timestamps are kept or
+//! dropped as a whole so a URL is never cut in half.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::block::{Counts, Hints};
+use crate::proto;
+use crate::proto::block::Inner;
+use crate::render::{Dialect, Renderer};
+
+/// The text appended to a block tree which has been truncated.
+pub(crate) const TRUNCATION_MARKER: &str = "…(truncated)";
+
+/// Returns the number of pieces a block can be cut into: one for each
+/// grapheme cluster of text or code, and one for each block which can't be
+/// cut.
+pub(crate) fn units(block: &proto::Block) -> usize {
+    match &block.inner {
+        Some(Inner::Text(text)) => text.text.graphemes(true).count(),
+        Some(Inner::InlineCode(code)) => code.text.graphemes(true).count(),
+        Some(Inner::FencedCode(code)) => code.text.graphemes(true).count(),
+        Some(Inner::Link(_)) | Some(Inner::Timestamp(_)) => 1,
+        Some(_) => crate::visit::children(block).iter().map(units).sum(),
+        None => 0,
+    }
+}
+
+/// Copies a block tree, keeping at most a given number of units.
+///
+/// Blocks which keep nothing are dropped, so the hints of the original tree
+/// are carried over to the blocks which are left as the cut goes.
+pub(crate) struct Cut<'h> {
+    hints: &'h Hints,
+    remaining: usize,
+    /// The hinted blocks walked so far in the original tree.
+    seen: Counts,
+    /// The hinted blocks kept so far in the cut tree.
+    kept: Counts,
+    kept_hints: Hints,
+}
+
+impl<'h> Cut<'h> {
+    pub(crate) fn new(hints: &'h Hints, units: usize) -> Self {
+        Self {
+            hints,
+            remaining: units,
+            seen: Counts::default(),
+            kept: Counts::default(),
+            kept_hints: Hints::default(),
+        }
+    }
+
+    /// Returns the hints for the blocks which have been kept.
+    pub(crate) fn finish(self) -> Hints {
+        self.kept_hints
+    }
+
+    /// Cuts each block in turn until the units run out. Blocks which keep
+    /// nothing, such as empty text, are skipped rather than ending the cut.
+    pub(crate) fn blocks(&mut self, blocks: &[proto::Block]) -> Vec<proto::Block> {
+        let mut kept = Vec::new();
+        for block in blocks {
+            if self.remaining == 0 {
+                break;
+            }
+            kept.extend(self.block(block));
+        }
+        kept
+    }
+
+    /// Returns a copy of the block with as many units as are left, or `None`
+    /// if nothing in it would be kept.
+    fn block(&mut self, block: &proto::Block) -> Option<proto::Block> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let inner = match block.inner.as_ref()? {
+            Inner::Text(text) => Inner::Text(proto::TextBlock {
+                text: self.take_graphemes(&text.text)?,
+            }),
+            Inner::InlineCode(code) => Inner::InlineCode(proto::InlineCodeBlock {
+                text: self.take_graphemes(&code.text)?,
+            }),
+            Inner::FencedCode(code) => Inner::FencedCode(proto::FencedCodeBlock {
+                info: code.info.clone(),
+                text: self.take_graphemes(&code.text)?,
+            }),
+            Inner::Link(_) | Inner::Timestamp(_) => {
+                self.remaining -= 1;
+                self.keep_whole(block);
+                return Some(block.clone());
+            }
+
+            Inner::Container(container) => Inner::Container(proto::ContainerBlock {
+                inner: self.children(&container.inner)?,
+            }),
+            Inner::List(list) => return self.list(block, list),
+
+            Inner::Bold(bold) => Inner::Bold(Box::new(proto::BoldBlock {
+                inner: self.single(&bold.inner)?,
+            })),
+            Inner::Italics(italics) => Inner::Italics(Box::new(proto::ItalicsBlock {
+                inner: self.single(&italics.inner)?,
+            })),
+            Inner::Underline(underline) => Inner::Underline(Box::new(proto::UnderlineBlock {
+                inner: self.single(&underline.inner)?,
+            })),
+            Inner::Strikethrough(strikethrough) => {
+                Inner::Strikethrough(Box::new(proto::StrikethroughBlock {
+                    inner: self.single(&strikethrough.inner)?,
+                }))
+            }
+            Inner::Spoiler(spoiler) => Inner::Spoiler(Box::new(proto::SpoilerBlock {
+                inner: self.single(&spoiler.inner)?,
+            })),
+            Inner::Blockquote(blockquote) => Inner::Blockquote(Box::new(proto::BlockquoteBlock {
+                inner: self.single(&blockquote.inner)?,
+            })),
+            Inner::Heading(heading) => Inner::Heading(Box::new(proto::HeadingBlock {
+                level: heading.level,
+                inner: self.single(&heading.inner)?,
+            })),
+        };
+
+        Some(proto::Block {
+            plain: String::new(),
+            inner: Some(inner),
+        })
+    }
+
+    fn list(&mut self, block: &proto::Block, list: &proto::ListBlock) -> Option<proto::Block> {
+        let start = self.hints.list_starts.get(&self.seen.lists).copied();
+        let own = self.kept;
+        self.seen.lists += 1;
+        self.kept.lists += 1;
+
+        let Some(inner) = self.children(&list.inner) else {
+            self.kept.lists -= 1;
+            return None;
+        };
+        let mut block = proto::Block {
+            plain: block.plain.clone(),
+            inner: Some(Inner::List(proto::ListBlock { inner })),
+        };
+
+        // Ordered lists keep their numbered fallback in sync with the items
+        // which are left.
+        if let Some(start) = start {
+            self.kept_hints.list_starts.insert(own.lists, start);
+
+            let hints = hints_from(&self.kept_hints, own);
+            let mut renderer = Renderer::new(Dialect::Plain, &hints);
+            renderer.block(&block);
+            block.plain = renderer.finish();
+        }
+        Some(block)
+    }
+
+    fn children(&mut self, children: &[proto::Block]) -> Option<Vec<proto::Block>> {
+        let kept = self.blocks(children);
+        (!kept.is_empty()).then_some(kept)
+    }
+
+    fn single(&mut self, inner: &Option<Box<proto::Block>>) -> Option<Option<Box<proto::Block>>> {
+        let inner = self.block(inner.as_deref()?)?;
+        Some(Some(Box::new(inner)))
+    }
+
+    /// Carries over the hints of a block which is kept without being cut.
+    fn keep_whole(&mut self, block: &proto::Block) {
+        let counts = Counts::of([block]);
+        for i in 0..counts.lists {
+            if let Some(&start) = self.hints.list_starts.get(&(self.seen.lists + i)) {
+                self.kept_hints
+                    .list_starts
+                    .insert(self.kept.lists + i, start);
+            }
+        }
+        for i in 0..counts.timestamps {
+            if let Some(&style) = self.hints.timestamp_styles.get(&(self.seen.timestamps + i)) {
+                self.kept_hints
+                    .timestamp_styles
+                    .insert(self.kept.timestamps + i, style);
+            }
+        }
+        self.seen += counts;
+        self.kept += counts;
+    }
+
+    /// Takes as many grapheme clusters from the start of the text as are
+    /// left.
+    fn take_graphemes(&mut self, text: &str) -> Option<String> {
+        let end = text
+            .grapheme_indices(true)
+            .nth(self.remaining)
+            .map_or(text.len(), |(i, _)| i);
+        self.remaining -= text[..end].graphemes(true).count();
+        (end > 0).then(|| text[..end].to_string())
+    }
+}
+
+/// Returns the hints for the blocks which come after `offset` blocks of each
+/// kind, renumbered from zero.
+fn hints_from(hints: &Hints, offset: Counts) -> Hints {
+    Hints {
+        list_starts: hints
+            .list_starts
+            .range(offset.lists..)
+            .map(|(i, start)| (i - offset.lists, *start))
+            .collect(),
+        timestamp_styles: hints
+            .timestamp_styles
+            .range(offset.timestamps..)
+            .map(|(i, style)| (i - offset.timestamps, *style))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::proto;
+    use crate::render::{Dialect, TimestampStyle};
+    use crate::Block;
+
+    #[test]
+    fn empty_children_are_skipped() {
+        let block = Block::new()
+            .text("Hello ")
+            .bold("")
+            .text("world and a lot more text here to cut")
+            .truncate(25, Dialect::Plain);
+        assert_eq!(block.render(Dialect::Plain), "Hello world a…(truncated)");
+
+        let block = Block::new()
+            .text("Hello ")
+            .container(Vec::<Block>::new())
+            .bold("world and a lot more text here to cut")
+            .truncate(25, Dialect::Markdown);
+        assert_eq!(block.render(Dialect::Markdown), "Hello **wor**…(truncated)");
+    }
+
+    #[test]
+    fn blocks_which_fit_are_unchanged() {
+        let block = Block::new().text("short").bold("text");
+        assert_eq!(
+            block.clone().truncate(9, Dialect::Plain).children(),
+            block.children()
+        );
+    }
+
+    #[test]
+    fn text_is_cut_between_grapheme_clusters() {
+        let block = Block::new()
+            .text("ne\u{301}e\u{301} and much more text")
+            .truncate(15, Dialect::Plain);
+        assert_eq!(block.render(Dialect::Plain), "ne\u{301}…(truncated)");
+    }
+
+    #[test]
+    fn links_are_kept_or_dropped_whole() {
+        let block = Block::new()
+            .text("See ")
+            .link("https://example.com", "the docs")
+            .text(" for more details");
+
+        assert_eq!(
+            block
+                .clone()
+                .truncate(47, Dialect::Markdown)
+                .render(Dialect::Markdown),
+            "See [the docs](https://example.com)…(truncated)"
+        );
+        assert_eq!(
+            block
+                .truncate(46, Dialect::Markdown)
+                .render(Dialect::Markdown),
+            "See …(truncated)"
+        );
+    }
+
+    #[test]
+    fn ordered_lists_keep_their_numbering() {
+        let block = Block::new()
+            .list(Vec::<Block>::new())
+            .ordered_list(3, vec!["first", "second", "third", "fourth"])
+            .truncate(31, Dialect::Plain);
+
+        assert_eq!(
+            block.render(Dialect::Plain),
+            "3. first\n4. second\n…(truncated)"
+        );
+        let proto: proto::Block = block.into();
+        assert_eq!(
+            crate::visit::children(&proto)[0].plain,
+            "3. first\n4. second"
+        );
+    }
+
+    #[test]
+    fn timestamps_keep_their_style() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let block = Block::new()
+            .styled_timestamp(time, TimestampStyle::ShortDate)
+            .text(" was the release date")
+            .truncate(30, Dialect::Discord);
+
+        assert_eq!(
+            block.render(Dialect::Discord),
+            "<t:1700000000:d> w…(truncated)"
+        );
+    }
+
+    #[test]
+    fn irc_lengths_are_in_bytes() {
+        let block = Block::new().text("héllo wörld");
+        assert_eq!(block.rendered_len(Dialect::Plain), 11);
+        assert_eq!(block.rendered_len(Dialect::Irc), 13);
+
+        let block = block.text(", and more").truncate(20, Dialect::Irc);
+        assert_eq!(block.render(Dialect::Irc), "héllo…(truncated)");
+        assert!(block.rendered_len(Dialect::Irc) <= 20);
+    }
+
+    #[test]
+    fn marker_is_cut_when_nothing_else_fits() {
+        let block = Block::new().text("this will not fit at all");
+        assert_eq!(
+            block
+                .clone()
+                .truncate(5, Dialect::Plain)
+                .render(Dialect::Plain),
+            "…(tru"
+        );
+        assert_eq!(block.truncate(5, Dialect::Irc).render(Dialect::Irc), "…(t");
+    }
+}
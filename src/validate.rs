@@ -0,0 +1,363 @@
+//! Validation of block trees.
+//!
+//! The [`Block`](crate::Block) builder accepts any input, so it's possible to
+//! build blocks which chat backends can't render, such as headings with a
+//! level of 0 or links with a `javascript:` URL. [`Block::validate`](crate::Block::validate)
+//! checks a block tree against a set of [`ValidationOptions`] and reports
+//! every problem it finds.
+
+use std::fmt;
+
+use crate::block::mention_user_id;
+use crate::proto;
+use crate::proto::block::Inner;
+use crate::visit::{self, Visitor};
+
+/// Limits used when validating a block tree.
+#[derive(Clone, Debug)]
+pub struct ValidationOptions {
+    /// The maximum nesting depth of the tree. Top-level blocks are at depth 1.
+    pub max_depth: usize,
+    /// The URL schemes allowed in links, in lowercase.
+    pub allowed_url_schemes: Vec<String>,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 32,
+            allowed_url_schemes: vec![
+                "http".to_string(),
+                "https".to_string(),
+                "mailto".to_string(),
+            ],
+        }
+    }
+}
+
+/// A single problem found while validating a block tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A heading level outside of the range 1 to 6.
+    InvalidHeadingLevel { level: i32 },
+    /// A link URL which could not be parsed.
+    InvalidUrl { url: String },
+    /// A link URL with a scheme which isn't in the allowed list.
+    DisallowedUrlScheme { url: String, scheme: String },
+    /// The tree is nested deeper than the allowed maximum. This is reported
+    /// at most once, and blocks past the limit aren't checked.
+    MaxDepthExceeded { max_depth: usize },
+    /// A formatting block, heading or list without any content.
+    EmptyFormatting { kind: &'static str },
+    /// A block without any content.
+    EmptyBlock,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidHeadingLevel { level } => {
+                write!(f, "heading level {} is not between 1 and 6", level)
+            }
+            ValidationError::InvalidUrl { url } => write!(f, "invalid link URL {:?}", url),
+            ValidationError::DisallowedUrlScheme { url, scheme } => {
+                write!(f, "link URL {:?} uses disallowed scheme {:?}", url, scheme)
+            }
+            ValidationError::MaxDepthExceeded { max_depth } => {
+                write!(f, "block is nested deeper than {} levels", max_depth)
+            }
+            ValidationError::EmptyFormatting { kind } => write!(f, "empty {} block", kind),
+            ValidationError::EmptyBlock => write!(f, "block has no content"),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// All of the problems found while validating a block tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<ValidationError>,
+}
+
+impl ValidationErrors {
+    /// Returns the individual validation errors, in the order they were found.
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+}
+
+impl IntoIterator for ValidationErrors {
+    type Item = ValidationError;
+    type IntoIter = std::vec::IntoIter<ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid block: ")?;
+        for (i, err) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", err)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+pub(crate) struct Validator<'o> {
+    options: &'o ValidationOptions,
+    depth: usize,
+    depth_exceeded: bool,
+    errors: Vec<ValidationError>,
+}
+
+impl<'o> Validator<'o> {
+    pub(crate) fn new(options: &'o ValidationOptions) -> Self {
+        Self {
+            options,
+            depth: 0,
+            depth_exceeded: false,
+            errors: Vec::new(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors {
+                errors: self.errors,
+            })
+        }
+    }
+
+    fn check_url(&mut self, url: &str) {
+        let Some((scheme, rest)) = url.split_once(':') else {
+            self.errors.push(ValidationError::InvalidUrl {
+                url: url.to_string(),
+            });
+            return;
+        };
+
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        let scheme = scheme.to_ascii_lowercase();
+        let needs_host = scheme == "http" || scheme == "https";
+        let has_host = rest
+            .strip_prefix("//")
+            .is_some_and(|rest| !rest.is_empty() && !rest.starts_with('/'));
+
+        if !valid_scheme || rest.is_empty() || (needs_host && !has_host) {
+            self.errors.push(ValidationError::InvalidUrl {
+                url: url.to_string(),
+            });
+        } else if !self.options.allowed_url_schemes.contains(&scheme) {
+            self.errors.push(ValidationError::DisallowedUrlScheme {
+                url: url.to_string(),
+                scheme,
+            });
+        }
+    }
+}
+
+/// Returns true if the given block has nothing to render.
+fn is_empty(block: &proto::Block) -> bool {
+    match &block.inner {
+        Some(Inner::Text(text)) => text.text.is_empty(),
+        Some(Inner::Container(container)) => container.inner.iter().all(is_empty),
+        Some(_) => false,
+        None => true,
+    }
+}
+
+fn is_empty_inner(inner: &Option<Box<proto::Block>>) -> bool {
+    inner.as_deref().is_none_or(is_empty)
+}
+
+impl<'a> Visitor<'a> for Validator<'_> {
+    fn visit_block(&mut self, block: &'a proto::Block) {
+        if self.depth >= self.options.max_depth {
+            if !self.depth_exceeded {
+                self.depth_exceeded = true;
+                self.errors.push(ValidationError::MaxDepthExceeded {
+                    max_depth: self.options.max_depth,
+                });
+            }
+            return;
+        }
+
+        let empty_kind = match &block.inner {
+            Some(Inner::Bold(bold)) if is_empty_inner(&bold.inner) => Some("bold"),
+            Some(Inner::Italics(italics)) if is_empty_inner(&italics.inner) => Some("italic"),
+            Some(Inner::Underline(underline)) if is_empty_inner(&underline.inner) => {
+                Some("underline")
+            }
+            Some(Inner::Strikethrough(strikethrough)) if is_empty_inner(&strikethrough.inner) => {
+                Some("strikethrough")
+            }
+            Some(Inner::Spoiler(spoiler)) if is_empty_inner(&spoiler.inner) => Some("spoiler"),
+            Some(Inner::Blockquote(blockquote)) if is_empty_inner(&blockquote.inner) => {
+                Some("blockquote")
+            }
+            Some(Inner::Heading(heading)) if is_empty_inner(&heading.inner) => Some("heading"),
+            Some(Inner::List(list)) if list.inner.is_empty() => Some("list"),
+            _ => None,
+        };
+        if let Some(kind) = empty_kind {
+            self.errors.push(ValidationError::EmptyFormatting { kind });
+        }
+
+        match &block.inner {
+            Some(Inner::Heading(heading)) if !(1..=6).contains(&heading.level) => {
+                self.errors.push(ValidationError::InvalidHeadingLevel {
+                    level: heading.level,
+                });
+            }
+            Some(Inner::Link(link)) if mention_user_id(link).is_none() => self.check_url(&link.url),
+            None => self.errors.push(ValidationError::EmptyBlock),
+            _ => {}
+        }
+
+        self.depth += 1;
+        visit::walk_block(self, block);
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Block;
+
+    fn errors(block: &Block, options: &ValidationOptions) -> Vec<ValidationError> {
+        match block.validate_with(options) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_iter().collect(),
+        }
+    }
+
+    fn nested(depth: usize) -> Block {
+        (1..depth).fold(Block::new().text("deep"), |block, _| {
+            Block::new().bold(block)
+        })
+    }
+
+    #[test]
+    fn accepts_valid_blocks() {
+        let block = Block::new()
+            .heading(1, "Title")
+            .text("See ")
+            .link("https://example.com/docs", "the docs")
+            .text(" or ")
+            .link("mailto:help@example.com", "email us")
+            .list(vec!["one", "two"]);
+
+        assert_eq!(block.validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_max_depth_once() {
+        let options = ValidationOptions {
+            max_depth: 3,
+            ..Default::default()
+        };
+
+        assert!(errors(&nested(3), &options).is_empty());
+
+        let block = Block::new()
+            .append(nested(10))
+            .append(nested(5))
+            .heading(0, "Title");
+        assert_eq!(
+            errors(&block, &options),
+            [
+                ValidationError::MaxDepthExceeded { max_depth: 3 },
+                ValidationError::InvalidHeadingLevel { level: 0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_checking_past_max_depth() {
+        let options = ValidationOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let block = Block::new().bold(Block::new().italic(Block::new().heading(9, "")));
+
+        assert_eq!(
+            errors(&block, &options),
+            [ValidationError::MaxDepthExceeded { max_depth: 2 }]
+        );
+    }
+
+    #[test]
+    fn checks_link_urls() {
+        let block = Block::new()
+            .link("javascript:alert(1)", "a")
+            .link("HTTPS://example.com", "b")
+            .link("https:///path", "c")
+            .link("not a url", "d")
+            .link("ftp:", "e");
+
+        assert_eq!(
+            errors(&block, &ValidationOptions::default()),
+            [
+                ValidationError::DisallowedUrlScheme {
+                    url: "javascript:alert(1)".to_string(),
+                    scheme: "javascript".to_string(),
+                },
+                ValidationError::InvalidUrl {
+                    url: "https:///path".to_string(),
+                },
+                ValidationError::InvalidUrl {
+                    url: "not a url".to_string(),
+                },
+                ValidationError::InvalidUrl {
+                    url: "ftp:".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_mention_urls() {
+        let block = Block::new().mention("1234", "belak");
+        assert_eq!(block.validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_empty_blocks() {
+        let block = Block::new()
+            .italic(Block::new().text(""))
+            .list(Vec::<Block>::new())
+            .append(proto::Block::default());
+
+        assert_eq!(
+            errors(&block, &ValidationOptions::default()),
+            [
+                ValidationError::EmptyFormatting { kind: "italic" },
+                ValidationError::EmptyFormatting { kind: "list" },
+                ValidationError::EmptyBlock,
+            ]
+        );
+    }
+
+    #[test]
+    fn displays_errors() {
+        let err = Block::new().heading(7, "Title").validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid block: heading level 7 is not between 1 and 6"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Traversal and transformation of block trees.
+//!
+//! This module provides three ways of walking a tree of [`proto::Block`]s:
+//!
+//! - [`Visitor`] for read-only traversal
+//! - [`VisitorMut`] for modifying blocks in place
+//! - [`Fold`] for rebuilding a tree by value, which allows replacing blocks
+//!   with different kinds of blocks
+//!
+//! Each trait has a single method which is called for every block in the tree
+//! in pre-order. The default implementations recurse into child blocks using
+//! [`walk_block`], [`walk_block_mut`] and [`fold_children`] respectively, so
+//! implementations which override them should call those functions to keep
+//! descending.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use seabird::visit::Visitor;
+//! use seabird::{proto, Block};
+//!
+//! #[derive(Default)]
+//! struct CountBold(usize);
+//!
+//! impl<'a> Visitor<'a> for CountBold {
+//!     fn visit_block(&mut self, block: &'a proto::Block) {
+//!         if let Some(proto::block::Inner::Bold(_)) = &block.inner {
+//!             self.0 += 1;
+//!         }
+//!         seabird::visit::walk_block(self, block);
+//!     }
+//! }
+//!
+//! let block = Block::new().bold("one").text(" and ").bold(Block::new().bold("two"));
+//!
+//! let mut counter = CountBold::default();
+//! block.visit(&mut counter);
+//! assert_eq!(counter.0, 3);
+//! ```
+
+use crate::block::mention_user_id;
+use crate::proto;
+use crate::proto::block::Inner;
+
+/// A read-only visitor over a block tree.
+pub trait Visitor<'a> {
+    /// Called for every block in the tree.
+    fn visit_block(&mut self, block: &'a proto::Block) {
+        walk_block(self, block);
+    }
+}
+
+/// A visitor which can modify blocks in place.
+pub trait VisitorMut {
+    /// Called for every block in the tree.
+    fn visit_block_mut(&mut self, block: &mut proto::Block) {
+        walk_block_mut(self, block);
+    }
+}
+
+/// A transformation which rebuilds a block tree by value.
+pub trait Fold {
+    /// Called for every block in the tree, returning its replacement.
+    fn fold_block(&mut self, block: proto::Block) -> proto::Block {
+        fold_children(self, block)
+    }
+}
+
+/// Visits each of the direct children of the given block.
+pub fn walk_block<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, block: &'a proto::Block) {
+    for child in children(block) {
+        visitor.visit_block(child);
+    }
+}
+
+/// Visits each of the direct children of the given block mutably.
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut proto::Block) {
+    for child in children_mut(block) {
+        visitor.visit_block_mut(child);
+    }
+}
+
+/// Replaces each of the direct children of the given block with the result of
+/// folding it.
+pub fn fold_children<F: Fold + ?Sized>(folder: &mut F, mut block: proto::Block) -> proto::Block {
+    for child in children_mut(&mut block) {
+        let folded = folder.fold_block(std::mem::take(child));
+        *child = folded;
+    }
+    block
+}
+
+/// Returns the direct children of the given block.
+///
+/// Formatting blocks, links and headings have at most one child, while
+/// containers and lists can have any number. Text, code and timestamp blocks
+/// have none.
+pub fn children(block: &proto::Block) -> &[proto::Block] {
+    fn single(inner: &Option<Box<proto::Block>>) -> &[proto::Block] {
+        inner
+            .as_deref()
+            .map(std::slice::from_ref)
+            .unwrap_or_default()
+    }
+
+    match &block.inner {
+        Some(Inner::Container(container)) => &container.inner,
+        Some(Inner::List(list)) => &list.inner,
+        Some(Inner::Bold(bold)) => single(&bold.inner),
+        Some(Inner::Italics(italics)) => single(&italics.inner),
+        Some(Inner::Underline(underline)) => single(&underline.inner),
+        Some(Inner::Strikethrough(strikethrough)) => single(&strikethrough.inner),
+        Some(Inner::Spoiler(spoiler)) => single(&spoiler.inner),
+        Some(Inner::Blockquote(blockquote)) => single(&blockquote.inner),
+        Some(Inner::Link(link)) => single(&link.inner),
+        Some(Inner::Heading(heading)) => single(&heading.inner),
+        Some(Inner::Text(_))
+        | Some(Inner::InlineCode(_))
+        | Some(Inner::FencedCode(_))
+        | Some(Inner::Timestamp(_))
+        | None => &[],
+    }
+}
+
+/// Returns the direct children of the given block mutably.
+pub fn children_mut(block: &mut proto::Block) -> &mut [proto::Block] {
+    fn single(inner: &mut Option<Box<proto::Block>>) -> &mut [proto::Block] {
+        inner
+            .as_deref_mut()
+            .map(std::slice::from_mut)
+            .unwrap_or_default()
+    }
+
+    match &mut block.inner {
+        Some(Inner::Container(container)) => &mut container.inner,
+        Some(Inner::List(list)) => &mut list.inner,
+        Some(Inner::Bold(bold)) => single(&mut bold.inner),
+        Some(Inner::Italics(italics)) => single(&mut italics.inner),
+        Some(Inner::Underline(underline)) => single(&mut underline.inner),
+        Some(Inner::Strikethrough(strikethrough)) => single(&mut strikethrough.inner),
+        Some(Inner::Spoiler(spoiler)) => single(&mut spoiler.inner),
+        Some(Inner::Blockquote(blockquote)) => single(&mut blockquote.inner),
+        Some(Inner::Link(link)) => single(&mut link.inner),
+        Some(Inner::Heading(heading)) => single(&mut heading.inner),
+        Some(Inner::Text(_))
+        | Some(Inner::InlineCode(_))
+        | Some(Inner::FencedCode(_))
+        | Some(Inner::Timestamp(_))
+        | None => &mut [],
+    }
+}
+
+/// Applies a function to the contents of every text block.
+pub(crate) struct MapText<F>(pub(crate) F);
+
+impl<F: FnMut(&str) -> String> VisitorMut for MapText<F> {
+    fn visit_block_mut(&mut self, block: &mut proto::Block) {
+        if let Some(Inner::Text(text)) = &mut block.inner {
+            text.text = (self.0)(&text.text);
+        }
+        walk_block_mut(self, block);
+    }
+}
+
+/// Collects every link block in a tree, other than mentions.
+#[derive(Default)]
+pub(crate) struct FindLinks<'a>(pub(crate) Vec<&'a proto::LinkBlock>);
+
+impl<'a> Visitor<'a> for FindLinks<'a> {
+    fn visit_block(&mut self, block: &'a proto::Block) {
+        if let Some(Inner::Link(link)) = &block.inner {
+            if mention_user_id(link).is_none() {
+                self.0.push(link);
+            }
+        }
+        walk_block(self, block);
+    }
+}
+
+/// Splices the children of nested containers into their parent container.
+pub(crate) struct Flatten;
+
+impl Fold for Flatten {
+    fn fold_block(&mut self, block: proto::Block) -> proto::Block {
+        let mut block = fold_children(self, block);
+        if let Some(Inner::Container(container)) = &mut block.inner {
+            container.inner = flatten_containers(std::mem::take(&mut container.inner));
+        }
+        block
+    }
+}
+
+/// Replaces any containers in the given list with their children.
+pub(crate) fn flatten_containers(blocks: Vec<proto::Block>) -> Vec<proto::Block> {
+    let mut flattened = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        match block.inner {
+            Some(Inner::Container(container)) => flattened.extend(container.inner),
+            _ => flattened.push(block),
+        }
+    }
+    flattened
+}
+
+/// Merges adjacent text blocks and removes trivial containers.
+pub(crate) struct Normalize;
+
+impl Fold for Normalize {
+    fn fold_block(&mut self, block: proto::Block) -> proto::Block {
+        let mut block = fold_children(self, block);
+        if let Some(Inner::Container(container)) = &mut block.inner {
+            container.inner = normalize_children(std::mem::take(&mut container.inner));
+            if container.inner.len() == 1 {
+                return container.inner.pop().unwrap();
+            }
+        }
+        block
+    }
+}
+
+/// Flattens nested containers in the given list, drops empty text blocks and
+/// merges adjacent text blocks.
+pub(crate) fn normalize_children(blocks: Vec<proto::Block>) -> Vec<proto::Block> {
+    let mut normalized: Vec<proto::Block> = Vec::with_capacity(blocks.len());
+    for block in flatten_containers(blocks) {
+        let Some(Inner::Text(text)) = &block.inner else {
+            normalized.push(block);
+            continue;
+        };
+
+        if text.text.is_empty() && block.plain.is_empty() {
+            continue;
+        }
+
+        match normalized.last_mut() {
+            Some(proto::Block {
+                plain,
+                inner: Some(Inner::Text(prev)),
+            }) => {
+                prev.text.push_str(&text.text);
+                plain.push_str(&block.plain);
+            }
+            _ => normalized.push(block),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Block;
+
+    /// Records the kind of every visited block.
+    #[derive(Default)]
+    struct Kinds(Vec<&'static str>);
+
+    impl<'a> Visitor<'a> for Kinds {
+        fn visit_block(&mut self, block: &'a proto::Block) {
+            self.0.push(match &block.inner {
+                Some(Inner::Text(_)) => "text",
+                Some(Inner::Bold(_)) => "bold",
+                Some(Inner::Link(_)) => "link",
+                Some(Inner::List(_)) => "list",
+                Some(Inner::Container(_)) => "container",
+                Some(Inner::InlineCode(_)) => "inline_code",
+                _ => "other",
+            });
+            walk_block(self, block);
+        }
+    }
+
+    /// Replaces inline code with bold text.
+    struct CodeToBold;
+
+    impl Fold for CodeToBold {
+        fn fold_block(&mut self, block: proto::Block) -> proto::Block {
+            match block.inner {
+                Some(Inner::InlineCode(code)) => Block::new().bold(code.text).into(),
+                _ => fold_children(self, block),
+            }
+        }
+    }
+
+    #[test]
+    fn visits_in_pre_order() {
+        let block = Block::new()
+            .text("a")
+            .bold(Block::new().link("https://example.com", "b"))
+            .list(vec![Block::new().text("c"), Block::new().inline_code("d")]);
+
+        let mut kinds = Kinds::default();
+        block.visit(&mut kinds);
+        assert_eq!(
+            kinds.0,
+            [
+                "text",
+                "bold",
+                "link",
+                "text",
+                "list",
+                "text",
+                "inline_code"
+            ]
+        );
+    }
+
+    #[test]
+    fn map_text_skips_code() {
+        let block = Block::new()
+            .text("hello ")
+            .bold("world")
+            .inline_code("code")
+            .map_text(|text| text.to_uppercase());
+
+        let expected = Block::new()
+            .text("HELLO ")
+            .bold("WORLD")
+            .inline_code("code");
+        assert_eq!(proto::Block::from(block), proto::Block::from(expected));
+    }
+
+    #[test]
+    fn fold_replaces_nested_blocks() {
+        let block = Block::new()
+            .inline_code("top")
+            .italic(Block::new().inline_code("nested"))
+            .fold(&mut CodeToBold);
+
+        let expected = Block::new().bold("top").italic(Block::new().bold("nested"));
+        assert_eq!(proto::Block::from(block), proto::Block::from(expected));
+    }
+
+    #[test]
+    fn flatten_splices_nested_containers() {
+        let block = Block::new()
+            .container(vec![
+                Block::new().text("a"),
+                Block::new().container(vec![Block::new().text("b").text("c")]),
+            ])
+            .bold(Block::new().container(vec![Block::new().text("d").text("e")]))
+            .flatten();
+
+        let expected = Block::new()
+            .text("a")
+            .text("b")
+            .text("c")
+            .bold(Block::new().text("d").text("e"));
+        assert_eq!(proto::Block::from(block), proto::Block::from(expected));
+    }
+
+    #[test]
+    fn normalize_merges_text_and_unwraps_containers() {
+        let block = Block::new()
+            .text("Hello")
+            .text("")
+            .container(vec![Block::new().text(", ").text("world")])
+            .bold(Block::new().container(vec![Block::new().text("a").text("b")]))
+            .normalize();
+
+        let expected = Block::new().text("Hello, world").bold("ab");
+        assert_eq!(proto::Block::from(block), proto::Block::from(expected));
+    }
+
+    #[test]
+    fn find_links_skips_mentions() {
+        let block = Block::new()
+            .mention("1234", "belak")
+            .link("https://example.com", "docs");
+
+        let urls: Vec<&str> = block
+            .find_links()
+            .iter()
+            .map(|link| link.url.as_str())
+            .collect();
+        assert_eq!(urls, ["https://example.com"]);
+    }
+
+    #[test]
+    fn leaf_blocks_have_no_children() {
+        let block: proto::Block = Block::new().fenced_code("rust", "fn main() {}").into();
+        assert!(children(&block).is_empty());
+        assert!(children(&proto::Block::default()).is_empty());
+    }
+}